@@ -0,0 +1,21 @@
+/// Build script for the safe bindings.
+///
+/// Enables the same `cfg` flags as `notmuch-sys`, so APIs missing from the installed libnotmuch
+/// are compiled out, and passes on the version of libnotmuch found.
+use std::env;
+
+fn main() {
+    let known = env::var("DEP_NOTMUCH_KNOWN_CFGS").unwrap_or_default();
+    let enabled = env::var("DEP_NOTMUCH_CFGS").unwrap_or_default();
+    let version = env::var("DEP_NOTMUCH_LIB_VERSION").unwrap_or_default();
+
+    for cfg in known.split(',').filter(|cfg| !cfg.is_empty()) {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+    for cfg in enabled.split(',').filter(|cfg| !cfg.is_empty()) {
+        println!("cargo:rustc-cfg={}", cfg);
+    }
+
+    // Read by `version::compiled`.
+    println!("cargo:rustc-env=NOTMUCH_LIB_VERSION={}", version);
+}
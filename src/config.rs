@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+#[cfg(notmuch_0_32)]
+use notmuch_sys::{
+    notmuch_config_key_t, notmuch_config_key_t_NOTMUCH_CONFIG_BACKUP_DIR,
+    notmuch_config_key_t_NOTMUCH_CONFIG_DATABASE_PATH,
+    notmuch_config_key_t_NOTMUCH_CONFIG_EXCLUDE_TAGS, notmuch_config_key_t_NOTMUCH_CONFIG_HOOK_DIR,
+    notmuch_config_key_t_NOTMUCH_CONFIG_MAIL_ROOT, notmuch_config_key_t_NOTMUCH_CONFIG_NEW_IGNORE,
+    notmuch_config_key_t_NOTMUCH_CONFIG_NEW_TAGS, notmuch_config_key_t_NOTMUCH_CONFIG_OTHER_EMAIL,
+    notmuch_config_key_t_NOTMUCH_CONFIG_PRIMARY_EMAIL,
+    notmuch_config_key_t_NOTMUCH_CONFIG_SYNC_MAILDIR_FLAGS,
+    notmuch_config_key_t_NOTMUCH_CONFIG_USER_NAME, notmuch_config_values_destroy,
+    notmuch_config_values_get, notmuch_config_values_move_to_next, notmuch_config_values_t,
+    notmuch_config_values_valid,
+};
+use notmuch_sys::{
+    notmuch_config_list_destroy, notmuch_config_list_key, notmuch_config_list_move_to_next,
+    notmuch_config_list_t, notmuch_config_list_valid, notmuch_config_list_value,
+};
+use std::{ffi::CStr, marker::PhantomData};
+
+/// Well-known configuration keys understood by `libnotmuch`.
+///
+/// Requires notmuch 0.32 or later.
+#[cfg(notmuch_0_32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigKey {
+    /// `database.path`
+    DatabasePath,
+    /// `database.mail_root`
+    MailRoot,
+    /// `database.hook_dir`
+    HookDir,
+    /// `database.backup_dir`
+    BackupDir,
+    /// `search.exclude_tags`
+    ExcludeTags,
+    /// `new.tags`
+    NewTags,
+    /// `new.ignore`
+    NewIgnore,
+    /// `maildir.synchronize_flags`
+    SyncMaildirFlags,
+    /// `user.primary_email`
+    PrimaryEmail,
+    /// `user.other_email`
+    OtherEmail,
+    /// `user.name`
+    UserName,
+}
+
+#[cfg(notmuch_0_32)]
+impl ConfigKey {
+    /// The key as it appears in `notmuch config` and in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigKey::DatabasePath => "database.path",
+            ConfigKey::MailRoot => "database.mail_root",
+            ConfigKey::HookDir => "database.hook_dir",
+            ConfigKey::BackupDir => "database.backup_dir",
+            ConfigKey::ExcludeTags => "search.exclude_tags",
+            ConfigKey::NewTags => "new.tags",
+            ConfigKey::NewIgnore => "new.ignore",
+            ConfigKey::SyncMaildirFlags => "maildir.synchronize_flags",
+            ConfigKey::PrimaryEmail => "user.primary_email",
+            ConfigKey::OtherEmail => "user.other_email",
+            ConfigKey::UserName => "user.name",
+        }
+    }
+}
+
+#[cfg(notmuch_0_32)]
+impl From<ConfigKey> for notmuch_config_key_t {
+    fn from(key: ConfigKey) -> Self {
+        match key {
+            ConfigKey::DatabasePath => notmuch_config_key_t_NOTMUCH_CONFIG_DATABASE_PATH,
+            ConfigKey::MailRoot => notmuch_config_key_t_NOTMUCH_CONFIG_MAIL_ROOT,
+            ConfigKey::HookDir => notmuch_config_key_t_NOTMUCH_CONFIG_HOOK_DIR,
+            ConfigKey::BackupDir => notmuch_config_key_t_NOTMUCH_CONFIG_BACKUP_DIR,
+            ConfigKey::ExcludeTags => notmuch_config_key_t_NOTMUCH_CONFIG_EXCLUDE_TAGS,
+            ConfigKey::NewTags => notmuch_config_key_t_NOTMUCH_CONFIG_NEW_TAGS,
+            ConfigKey::NewIgnore => notmuch_config_key_t_NOTMUCH_CONFIG_NEW_IGNORE,
+            ConfigKey::SyncMaildirFlags => notmuch_config_key_t_NOTMUCH_CONFIG_SYNC_MAILDIR_FLAGS,
+            ConfigKey::PrimaryEmail => notmuch_config_key_t_NOTMUCH_CONFIG_PRIMARY_EMAIL,
+            ConfigKey::OtherEmail => notmuch_config_key_t_NOTMUCH_CONFIG_OTHER_EMAIL,
+            ConfigKey::UserName => notmuch_config_key_t_NOTMUCH_CONFIG_USER_NAME,
+        }
+    }
+}
+
+/// Iterator over `(key, value)` pairs stored in the database configuration.
+pub struct ConfigList<'a> {
+    ptr: *mut notmuch_config_list_t,
+    _covariant: PhantomData<&'a ()>,
+}
+
+impl Drop for ConfigList<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            notmuch_config_list_destroy(self.ptr);
+        }
+    }
+}
+
+impl From<*mut notmuch_config_list_t> for ConfigList<'_> {
+    fn from(ptr: *mut notmuch_config_list_t) -> Self {
+        ConfigList {
+            ptr,
+            _covariant: PhantomData,
+        }
+    }
+}
+
+impl Iterator for ConfigList<'_> {
+    type Item = (String, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if unsafe { notmuch_config_list_valid(self.ptr) } == 0 {
+            return None;
+        }
+
+        let (key, value) = unsafe {
+            (
+                CStr::from_ptr(notmuch_config_list_key(self.ptr)),
+                CStr::from_ptr(notmuch_config_list_value(self.ptr)),
+            )
+        };
+        let item = (
+            key.to_string_lossy().into_owned(),
+            value.to_string_lossy().into_owned(),
+        );
+
+        unsafe { notmuch_config_list_move_to_next(self.ptr) }
+
+        Some(item)
+    }
+}
+
+/// Iterator over the individual values of a list-valued configuration key, e.g. `new.tags`.
+///
+/// Requires notmuch 0.32 or later.
+#[cfg(notmuch_0_32)]
+pub struct ConfigValues<'a> {
+    ptr: *mut notmuch_config_values_t,
+    _covariant: PhantomData<&'a ()>,
+}
+
+#[cfg(notmuch_0_32)]
+impl ConfigValues<'_> {
+    /// Creates a `ConfigValues` iterator from a given raw `ptr`
+    ///
+    /// If `ptr` is NULL, this returns None.
+    pub fn from(ptr: *mut notmuch_config_values_t) -> Option<Self> {
+        if ptr.is_null() {
+            return None;
+        }
+
+        Some(ConfigValues {
+            ptr,
+            _covariant: PhantomData,
+        })
+    }
+}
+
+#[cfg(notmuch_0_32)]
+impl Drop for ConfigValues<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            notmuch_config_values_destroy(self.ptr);
+        }
+    }
+}
+
+#[cfg(notmuch_0_32)]
+impl Iterator for ConfigValues<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if unsafe { notmuch_config_values_valid(self.ptr) } == 0 {
+            return None;
+        }
+
+        let value = unsafe { CStr::from_ptr(notmuch_config_values_get(self.ptr)) }
+            .to_string_lossy()
+            .into_owned();
+
+        unsafe { notmuch_config_values_move_to_next(self.ptr) }
+
+        Some(value)
+    }
+}
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Session keys of encrypted messages.
+//!
+//! Indexing with `DecryptPolicy::True` stashes the session key of each decrypted message in its
+//! `session-key` property. A session key decrypts that one message without the user's secret key,
+//! so keeping them allows reading and reindexing mail after the secret key is gone, e.g. expired
+//! or revoked.
+//!
+//! Session keys can be exported and imported in the property format of `notmuch dump`, so an
+//! export can also be restored with `notmuch restore --include=properties`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let file = File::create("session-keys")?;
+//! let exported = crypto::export_session_keys(&db, file)?;
+//!
+//! let file = BufReader::new(File::open("session-keys")?);
+//! let imported = crypto::import_session_keys(&other_db, file)?;
+//! ```
+use crate::{
+    db::Database,
+    dump::{hex_decode, hex_encode},
+    message::Message,
+    query::{Query, Sort},
+    status::Status,
+};
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, BufRead, Write},
+};
+
+/// Property holding the session keys of a message.
+pub const SESSION_KEY_PROPERTY: &str = "session-key";
+
+/// First line of an export, as written by `notmuch dump --include=properties`.
+const DUMP_HEADER: &str = "#notmuch-dump batch-tag:3 properties";
+
+/// Prefix of a line with the properties of one message.
+const PROPERTY_LINE_PREFIX: &str = "#= ";
+
+/// Error exporting or importing session keys.
+#[derive(Debug)]
+pub enum SessionKeyError {
+    /// Reading or writing the dump failed.
+    Io(io::Error),
+    /// A line of the dump couldn't be parsed. Lines are numbered from 1.
+    InvalidLine(usize),
+    /// A notmuch operation failed.
+    Status(Status),
+}
+
+impl Error for SessionKeyError {}
+
+impl Display for SessionKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionKeyError::Io(e) => write!(f, "{}", e),
+            SessionKeyError::InvalidLine(line) => write!(f, "Invalid property line {}", line),
+            SessionKeyError::Status(st) => write!(f, "{}", st),
+        }
+    }
+}
+
+impl From<io::Error> for SessionKeyError {
+    fn from(e: io::Error) -> Self {
+        SessionKeyError::Io(e)
+    }
+}
+
+impl From<Status> for SessionKeyError {
+    fn from(st: Status) -> Self {
+        SessionKeyError::Status(st)
+    }
+}
+
+impl Message<'_> {
+    /// Returns the stashed session keys of the message.
+    ///
+    /// A message usually has at most one, but may have several, e.g. if it was encrypted
+    /// several times.
+    pub fn session_keys(&self) -> Vec<String> {
+        self.properties(SESSION_KEY_PROPERTY, true)
+            .map(|properties| properties.map(|(_, value)| value).collect())
+            .unwrap_or_default()
+    }
+
+    /// Stash `key` as a session key of the message.
+    pub fn add_session_key(&self, key: &str) -> Result<(), Status> {
+        self.add_property(SESSION_KEY_PROPERTY, key)
+    }
+
+    /// Remove the stashed session key `key` from the message.
+    pub fn remove_session_key(&self, key: &str) -> Result<(), Status> {
+        self.remove_property(SESSION_KEY_PROPERTY, key)
+    }
+}
+
+/// Write the session keys of all messages in `db` to `writer`, in `notmuch dump` format.
+///
+/// Messages are ordered by message ID and those without session keys are left out. Returns the
+/// number of session keys written.
+pub fn export_session_keys<W: Write>(
+    db: &Database,
+    mut writer: W,
+) -> Result<usize, SessionKeyError> {
+    let query = Query::create(db, "*");
+    query.set_sort(Sort::MessageId);
+
+    writeln!(writer, "{}", DUMP_HEADER)?;
+    let mut count = 0;
+
+    for msg in &query.search_messages()? {
+        let keys = msg.session_keys();
+
+        if keys.is_empty() {
+            continue;
+        }
+
+        write!(
+            writer,
+            "{}{}",
+            PROPERTY_LINE_PREFIX,
+            hex_encode(&msg.message_id())
+        )?;
+        for key in &keys {
+            write!(
+                writer,
+                " {}={}",
+                hex_encode(SESSION_KEY_PROPERTY),
+                hex_encode(key)
+            )?;
+        }
+        writeln!(writer)?;
+
+        count += keys.len();
+    }
+
+    writer.flush()?;
+
+    Ok(count)
+}
+
+/// Stash the session keys in `reader`, in `notmuch dump` format, on the messages of `db`.
+///
+/// Properties other than session keys, tags and comments are ignored, so a complete dump can be
+/// imported as well. Session keys of messages missing from `db` are skipped. Returns the number
+/// of session keys stashed, including ones the messages already had.
+pub fn import_session_keys<R: BufRead>(db: &Database, reader: R) -> Result<usize, SessionKeyError> {
+    let mut count = 0;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        let properties = match line.strip_prefix(PROPERTY_LINE_PREFIX) {
+            Some(properties) => properties,
+            None => continue,
+        };
+
+        let invalid = || SessionKeyError::InvalidLine(i + 1);
+        let mut words = properties.split(' ');
+        let id = words.next().and_then(hex_decode).ok_or_else(invalid)?;
+
+        let mut keys = Vec::new();
+        for word in words {
+            let (key, value) = word.split_once('=').ok_or_else(invalid)?;
+            let key = hex_decode(key).ok_or_else(invalid)?;
+
+            if key == SESSION_KEY_PROPERTY {
+                keys.push(hex_decode(value).ok_or_else(invalid)?);
+            }
+        }
+
+        if keys.is_empty() {
+            continue;
+        }
+
+        let msg = match db.find_message(&id)? {
+            Some(msg) => msg,
+            None => continue,
+        };
+
+        for key in &keys {
+            msg.add_session_key(key)?;
+        }
+
+        count += keys.len();
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,358 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Date ranges for `date:` queries.
+//!
+//! A `DateRange` is built from `SystemTime`s or parsed from notmuch-style date expressions such
+//! as `yesterday`, `3days` or `2024-01-01..last month`, and rendered as a `date:` term with
+//! exact timestamps. Calendar dates are interpreted in the local time zone, like notmuch does.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let range = DateRange::parse("last week..today")?;
+//! let expr = QueryExpr::tag("inbox").and(range.into());
+//! let query = Query::create(&db, &expr.to_string());
+//! ```
+use crate::expr::{Prefix, QueryExpr};
+use std::{
+    convert::TryFrom,
+    error::Error,
+    fmt::Display,
+    ops::Range,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Error for a date expression that couldn't be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateError {
+    /// The offending date expression.
+    pub input: String,
+}
+
+impl Error for DateError {}
+
+impl Display for DateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unrecognized date `{}`", self.input)
+    }
+}
+
+/// Range of message dates, with either side optionally open.
+///
+/// Both bounds are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+}
+
+impl DateRange {
+    /// Messages dated between `since` and `until`.
+    pub fn between(since: SystemTime, until: SystemTime) -> Self {
+        DateRange {
+            since: Some(since),
+            until: Some(until),
+        }
+    }
+
+    /// Messages dated `since` or later.
+    pub fn since(since: SystemTime) -> Self {
+        DateRange {
+            since: Some(since),
+            until: None,
+        }
+    }
+
+    /// Messages dated `until` or earlier.
+    pub fn until(until: SystemTime) -> Self {
+        DateRange {
+            since: None,
+            until: Some(until),
+        }
+    }
+
+    /// Parse a notmuch-style range such as `2024-01-01..yesterday`, relative to the current time.
+    ///
+    /// See `parse_at`.
+    pub fn parse(range: &str) -> Result<Self, DateError> {
+        Self::parse_at(range, SystemTime::now())
+    }
+
+    /// Parse a notmuch-style range, with relative dates taken relative to `now`.
+    ///
+    /// Each side is a date expression understood by `interval`. The range starts at the
+    /// beginning of the `since` interval and ends at the end of the `until` interval, so
+    /// `yesterday..yesterday` covers all of yesterday. Either side may be empty to leave it
+    /// open; a single date expression without `..` is the range covering just that date.
+    pub fn parse_at(range: &str, now: SystemTime) -> Result<Self, DateError> {
+        let (since, until) = match range.find("..") {
+            Some(i) => (range[..i].trim(), range[i + 2..].trim()),
+            None => (range.trim(), range.trim()),
+        };
+
+        if since.is_empty() && until.is_empty() {
+            return Err(DateError {
+                input: range.to_string(),
+            });
+        }
+
+        let since = match since {
+            "" => None,
+            since => Some(interval(since, now)?.start),
+        };
+        let until = match until {
+            "" => None,
+            until => Some(interval(until, now)?.end - Duration::from_secs(1)),
+        };
+
+        Ok(DateRange { since, until })
+    }
+
+    /// Returns the `date:` query expression for this range.
+    pub fn to_expr(&self) -> QueryExpr {
+        QueryExpr::Range(
+            Prefix::Date,
+            self.since.map(|since| format!("@{}", timestamp(since))),
+            self.until.map(|until| format!("@{}", timestamp(until))),
+        )
+    }
+}
+
+impl From<DateRange> for QueryExpr {
+    fn from(range: DateRange) -> Self {
+        range.to_expr()
+    }
+}
+
+/// Unit of time in a date expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    /// Parse a unit, ignoring case except for `M`, which is months rather than minutes.
+    fn parse(unit: &str) -> Option<Self> {
+        if unit == "M" {
+            return Some(Unit::Month);
+        }
+
+        let unit = match unit.to_lowercase().as_str() {
+            "s" | "sec" | "secs" | "second" | "seconds" => Unit::Second,
+            "m" | "min" | "mins" | "minute" | "minutes" => Unit::Minute,
+            "h" | "hr" | "hrs" | "hour" | "hours" => Unit::Hour,
+            "d" | "day" | "days" => Unit::Day,
+            "w" | "week" | "weeks" => Unit::Week,
+            "mon" | "month" | "months" => Unit::Month,
+            "y" | "yr" | "yrs" | "year" | "years" => Unit::Year,
+            _ => return None,
+        };
+
+        Some(unit)
+    }
+}
+
+/// Parse a single date expression into the interval of time it denotes, relative to `now`.
+///
+/// Understood expressions are:
+///
+/// - `now`, `today`, `yesterday`
+/// - `this week`, `last month` and so on, for calendar units; weeks start on Monday
+/// - `3days`, `3 days` or `3 days ago` for the day three days ago, and likewise for seconds,
+///   minutes, hours, weeks, months and years; `m` is minutes and `M` is months
+/// - `2024`, `2024-01` and `2024-01-31` for calendar dates, with four-digit years
+/// - `@1700000000` for a Unix timestamp
+///
+/// Words may also be separated by `.` or `_`, e.g. `2.weeks`.
+pub fn interval(expr: &str, now: SystemTime) -> Result<Range<SystemTime>, DateError> {
+    let error = || DateError {
+        input: expr.to_string(),
+    };
+
+    let normalized = expr.trim().replace(['.', '_'], " ");
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+    // Keywords are matched in lowercase, units as written since `m` and `M` differ.
+    let lowercase = normalized.to_lowercase();
+    let keywords: Vec<&str> = lowercase.split_whitespace().collect();
+    let now = timestamp(now);
+
+    let (start, unit) = match keywords.as_slice() {
+        ["now"] => (now, Unit::Second),
+        ["today"] => (now, Unit::Day),
+        ["yesterday"] => (
+            floor(now, Unit::Day)
+                .and_then(|today| add(today, Unit::Day, -1))
+                .ok_or_else(error)?,
+            Unit::Day,
+        ),
+        ["this", _] => (now, Unit::parse(words[1]).ok_or_else(error)?),
+        ["last", _] => relative("1", words[1], now).ok_or_else(error)?,
+        [word] if word.starts_with('@') => {
+            let secs = word[1..].parse().map_err(|_| error())?;
+            (secs, Unit::Second)
+        }
+        [word] if word.starts_with(|c: char| c.is_ascii_digit()) => {
+            match word.find(|c: char| !c.is_ascii_digit()) {
+                Some(i) if !word[i..].starts_with('-') => {
+                    relative(&words[0][..i], &words[0][i..], now).ok_or_else(error)?
+                }
+                _ => calendar_date(word).ok_or_else(error)?,
+            }
+        }
+        [_, _] | [_, _, "ago"] => relative(words[0], words[1], now).ok_or_else(error)?,
+        _ => return Err(error()),
+    };
+
+    let start = floor(start, unit).ok_or_else(error)?;
+    let end = add(start, unit, 1).ok_or_else(error)?;
+
+    Ok(system_time(start)..system_time(end))
+}
+
+/// Time `count` units before `now`.
+fn relative(count: &str, unit: &str, now: i64) -> Option<(i64, Unit)> {
+    let count: i64 = count.parse().ok()?;
+    let unit = Unit::parse(unit)?;
+
+    // Going back from the start of the unit avoids overflowing into the next month, e.g. from
+    // March 31st to "February 31st".
+    Some((add(floor(now, unit)?, unit, -count)?, unit))
+}
+
+/// Parse `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+fn calendar_date(date: &str) -> Option<(i64, Unit)> {
+    let mut parts = date.split('-');
+    // Only four-digit years, so a bare timestamp isn't taken for a year.
+    let year: i32 = parts.next().filter(|year| year.len() == 4)?.parse().ok()?;
+
+    let mut tm = empty_tm();
+    tm.tm_year = year - 1900;
+    tm.tm_mday = 1;
+
+    let unit = match (parts.next(), parts.next(), parts.next()) {
+        (None, _, _) => Unit::Year,
+        (Some(month), None, _) => {
+            tm.tm_mon = month_index(month)?;
+            Unit::Month
+        }
+        (Some(month), Some(day), None) => {
+            tm.tm_mon = month_index(month)?;
+            tm.tm_mday = day.parse().ok().filter(|day| (1..=31).contains(day))?;
+            Unit::Day
+        }
+        _ => return None,
+    };
+
+    Some((mktime(&mut tm)?, unit))
+}
+
+fn month_index(month: &str) -> Option<i32> {
+    month
+        .parse::<i32>()
+        .ok()
+        .filter(|month| (1..=12).contains(month))
+        .map(|month| month - 1)
+}
+
+fn timestamp(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+fn system_time(timestamp: i64) -> SystemTime {
+    if timestamp >= 0 {
+        UNIX_EPOCH + Duration::from_secs(timestamp as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(timestamp.unsigned_abs())
+    }
+}
+
+fn empty_tm() -> libc::tm {
+    // All-zero is a valid `tm`; a null `tm_zone` is fine for `mktime`.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_isdst = -1;
+    tm
+}
+
+fn local_time(timestamp: i64) -> libc::tm {
+    let mut tm = empty_tm();
+    unsafe { libc::localtime_r(&(timestamp as libc::time_t), &mut tm) };
+    tm
+}
+
+/// Returns `None` if the time can't be represented.
+fn mktime(tm: &mut libc::tm) -> Option<i64> {
+    // Let `mktime` work out whether daylight saving time applies.
+    tm.tm_isdst = -1;
+
+    match unsafe { libc::mktime(tm) as i64 } {
+        -1 => None,
+        time => Some(time),
+    }
+}
+
+/// Start of the `unit` containing `timestamp`.
+fn floor(timestamp: i64, unit: Unit) -> Option<i64> {
+    let mut tm = local_time(timestamp);
+
+    match unit {
+        Unit::Second => return Some(timestamp),
+        Unit::Minute => tm.tm_sec = 0,
+        Unit::Hour => {
+            tm.tm_sec = 0;
+            tm.tm_min = 0;
+        }
+        Unit::Day | Unit::Week | Unit::Month | Unit::Year => {
+            tm.tm_sec = 0;
+            tm.tm_min = 0;
+            tm.tm_hour = 0;
+        }
+    }
+
+    match unit {
+        Unit::Week => tm.tm_mday -= (tm.tm_wday + 6) % 7,
+        Unit::Month => tm.tm_mday = 1,
+        Unit::Year => {
+            tm.tm_mday = 1;
+            tm.tm_mon = 0;
+        }
+        _ => (),
+    }
+
+    mktime(&mut tm)
+}
+
+/// `timestamp` moved by `count` units, keeping the local time of day for calendar units.
+fn add(timestamp: i64, unit: Unit, count: i64) -> Option<i64> {
+    let secs: i64 = match unit {
+        Unit::Second => 1,
+        Unit::Minute => 60,
+        Unit::Hour => 3600,
+        _ => 0,
+    };
+
+    if secs > 0 {
+        return secs
+            .checked_mul(count)
+            .and_then(|secs| timestamp.checked_add(secs));
+    }
+
+    let mut tm = local_time(timestamp);
+    let count = i32::try_from(count).ok()?;
+
+    match unit {
+        Unit::Day => tm.tm_mday = tm.tm_mday.checked_add(count)?,
+        Unit::Week => tm.tm_mday = tm.tm_mday.checked_add(count.checked_mul(7)?)?,
+        Unit::Month => tm.tm_mon = tm.tm_mon.checked_add(count)?,
+        _ => tm.tm_year = tm.tm_year.checked_add(count)?,
+    }
+
+    mktime(&mut tm)
+}
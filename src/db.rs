@@ -1,14 +1,53 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
-use crate::status::Status;
+#[cfg(notmuch_0_32)]
+use crate::config::{ConfigKey, ConfigValues};
+use crate::{
+    config::ConfigList,
+    directory::Directory,
+    index::{IndexError, IndexOptions},
+    message::Message,
+    query::Query,
+    status::Status,
+    version::Capability,
+};
+#[cfg(notmuch_0_32)]
+use notmuch_sys::{
+    notmuch_config_get, notmuch_config_get_bool, notmuch_config_get_values, notmuch_config_set,
+    notmuch_database_open_with_config, notmuch_database_reopen,
+};
 use notmuch_sys::{
-    notmuch_database_destroy, notmuch_database_mode_t,
+    notmuch_database_begin_atomic, notmuch_database_close, notmuch_database_compact,
+    notmuch_database_create, notmuch_database_destroy, notmuch_database_end_atomic,
+    notmuch_database_find_message, notmuch_database_find_message_by_filename,
+    notmuch_database_get_config, notmuch_database_get_config_list,
+    notmuch_database_get_default_indexopts, notmuch_database_get_directory,
+    notmuch_database_get_path, notmuch_database_get_revision, notmuch_database_get_version,
+    notmuch_database_index_file, notmuch_database_mode_t,
     notmuch_database_mode_t_NOTMUCH_DATABASE_MODE_READ_ONLY,
-    notmuch_database_mode_t_NOTMUCH_DATABASE_MODE_READ_WRITE, notmuch_database_open,
-    notmuch_database_t,
+    notmuch_database_mode_t_NOTMUCH_DATABASE_MODE_READ_WRITE, notmuch_database_needs_upgrade,
+    notmuch_database_open, notmuch_database_remove_message, notmuch_database_set_config,
+    notmuch_database_t, notmuch_database_upgrade, notmuch_indexopts_t,
+};
+use std::{
+    any::Any,
+    ffi::{CStr, CString, OsStr},
+    os::{
+        raw::{c_char, c_void},
+        unix::ffi::OsStrExt,
+    },
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+#[cfg(notmuch_0_32)]
+use std::{
+    error::Error,
+    fmt::Display,
+    thread,
+    time::{Duration, Instant},
 };
-use std::{ffi::CString, path::Path};
 
 /// Should database be opened in read-only or read-write mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OpenMode {
     /// Open database for reading only.
     ReadOnly,
@@ -25,6 +64,73 @@ impl From<OpenMode> for notmuch_database_mode_t {
     }
 }
 
+/// How `Database::open_with_retry` waits for the write lock held by another writer.
+///
+/// The delay between attempts starts at `initial_delay` and doubles after every attempt, up to
+/// `max_delay`.
+///
+/// Requires notmuch 0.32 or later.
+#[cfg(notmuch_0_32)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockRetry {
+    /// Delay before the first retry.
+    pub initial_delay: Duration,
+    /// Upper bound for the delay between retries.
+    pub max_delay: Duration,
+    /// Total time after which to give up waiting.
+    pub timeout: Duration,
+}
+
+#[cfg(notmuch_0_32)]
+impl Default for LockRetry {
+    fn default() -> Self {
+        LockRetry {
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(1),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Error opening a database with `Database::open_with_retry`.
+///
+/// Requires notmuch 0.32 or later.
+#[cfg(notmuch_0_32)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpenError {
+    /// Another writer, e.g. a running `notmuch new`, kept the write lock until the timeout.
+    DatabaseLocked,
+    /// Opening the database failed for another reason.
+    Status(Status),
+}
+
+#[cfg(notmuch_0_32)]
+impl Error for OpenError {}
+
+#[cfg(notmuch_0_32)]
+impl Display for OpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpenError::DatabaseLocked => write!(f, "Database is locked by another writer"),
+            OpenError::Status(st) => write!(f, "{}", st),
+        }
+    }
+}
+
+#[cfg(notmuch_0_32)]
+impl From<Status> for OpenError {
+    fn from(st: Status) -> Self {
+        OpenError::Status(st)
+    }
+}
+
+/// Number of messages reindexed per atomic section by `Database::reindex_query`.
+const REINDEX_BATCH_SIZE: usize = 100;
+
+/// Part of the Xapian error message when the write lock is held by someone else.
+#[cfg(notmuch_0_32)]
+const WRITE_LOCK_ERROR: &str = "Unable to get write lock";
+
 /// Notmuch database struct.
 #[derive(Debug, PartialEq)]
 pub struct Database {
@@ -32,6 +138,344 @@ pub struct Database {
 }
 
 impl Database {
+    /// Begin an atomic database operation.
+    ///
+    /// Changes made until the matching `end_atomic` are either all applied or, if the database is
+    /// closed before that, all discarded. Atomic sections may be nested.
+    pub fn begin_atomic(&self) -> Result<(), Status> {
+        let st = unsafe { notmuch_database_begin_atomic(self.ptr) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
+    /// Run `f` within an atomic section, which is ended even if `f` fails.
+    ///
+    /// Leaving the section open would discard every later change once the database is closed.
+    pub(crate) fn atomic<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<Status>,
+    {
+        self.begin_atomic()?;
+        let res = f();
+        let end = self.end_atomic();
+
+        let value = res?;
+        end?;
+
+        Ok(value)
+    }
+
+    /// Commit all changes and close the database.
+    ///
+    /// This releases the write lock of a database opened in read-write mode, so other processes
+    /// can write to it. Afterwards, the database must not be used anymore, and is only destroyed
+    /// once dropped. Closing an already closed database is a no-op.
+    pub fn close(&self) -> Result<(), Status> {
+        let st = unsafe { notmuch_database_close(self.ptr) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
+    /// Compact the database at `path`, rewriting it into its smallest possible size.
+    ///
+    /// If `backup` is given, the original database is moved there; otherwise it is deleted once
+    /// compaction succeeded. `progress` is called with informational messages along the way. The
+    /// database is locked for writing while it is compacted, so it must not be open in read-write
+    /// mode elsewhere.
+    pub fn compact<F>(path: &Path, backup: Option<&Path>, progress: F) -> Result<(), Status>
+    where
+        F: FnMut(&str),
+    {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let backup = backup.map(|backup| CString::new(backup.as_os_str().as_bytes()).unwrap());
+        let mut callback = Callback::new(progress);
+
+        let st = unsafe {
+            notmuch_database_compact(
+                path.as_ptr(),
+                backup
+                    .as_ref()
+                    .map_or(std::ptr::null(), |backup| backup.as_ptr()),
+                Some(compact_status::<F>),
+                &mut callback as *mut Callback<F> as *mut c_void,
+            )
+        }
+        .into();
+
+        callback.resume_panic();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
+    /// Get the value of the configuration `key` stored in the database.
+    ///
+    /// Keys that have never been set yield an empty string.
+    pub fn config(&self, key: &str) -> Result<String, Status> {
+        let key = CString::new(key).unwrap();
+        let mut value = std::ptr::null_mut();
+
+        let st = unsafe { notmuch_database_get_config(self.ptr, key.as_ptr(), &mut value) }.into();
+
+        match st {
+            Status::Success => {
+                let owned = unsafe { CStr::from_ptr(value) }
+                    .to_string_lossy()
+                    .into_owned();
+                unsafe { libc::free(value as *mut libc::c_void) };
+                Ok(owned)
+            }
+            _ => Err(st),
+        }
+    }
+
+    /// Returns a `ConfigList` iterator over every stored configuration key starting with
+    /// `prefix`.
+    pub fn config_list(&self, prefix: &str) -> Result<ConfigList<'_>, Status> {
+        let prefix = CString::new(prefix).unwrap();
+        let mut list = std::ptr::null_mut();
+
+        let st = unsafe { notmuch_database_get_config_list(self.ptr, prefix.as_ptr(), &mut list) }
+            .into();
+
+        match st {
+            Status::Success => Ok(ConfigList::from(list)),
+            _ => Err(st),
+        }
+    }
+
+    /// Get the value of a well-known configuration `key`.
+    ///
+    /// Unlike `config`, this takes defaults and the configuration file into account. List-valued
+    /// keys are returned `;`-separated, use `config_values` to split them.
+    ///
+    /// Requires notmuch 0.32 or later.
+    #[cfg(notmuch_0_32)]
+    pub fn config_key(&self, key: ConfigKey) -> Option<String> {
+        let value = unsafe { notmuch_config_get(self.ptr, key.into()) };
+
+        if value.is_null() {
+            return None;
+        }
+
+        Some(
+            unsafe { CStr::from_ptr(value) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    /// Get the value of a well-known boolean configuration `key`.
+    ///
+    /// Requires notmuch 0.32 or later.
+    #[cfg(notmuch_0_32)]
+    pub fn config_bool(&self, key: ConfigKey) -> Result<bool, Status> {
+        let mut value = 0;
+
+        let st = unsafe { notmuch_config_get_bool(self.ptr, key.into(), &mut value) }.into();
+
+        match st {
+            Status::Success => Ok(value != 0),
+            _ => Err(st),
+        }
+    }
+
+    /// Returns a `ConfigValues` iterator over the entries of a list-valued configuration `key`.
+    ///
+    /// Requires notmuch 0.32 or later.
+    #[cfg(notmuch_0_32)]
+    pub fn config_values(&self, key: ConfigKey) -> Option<ConfigValues<'_>> {
+        let values = unsafe { notmuch_config_get_values(self.ptr, key.into()) };
+        ConfigValues::from(values)
+    }
+
+    /// Store `value` under the configuration `key` in the database.
+    ///
+    /// Requires the database to be opened in read-write mode.
+    pub fn set_config(&self, key: &str, value: &str) -> Result<(), Status> {
+        let key = CString::new(key).unwrap();
+        let value = CString::new(value).unwrap();
+
+        let st =
+            unsafe { notmuch_database_set_config(self.ptr, key.as_ptr(), value.as_ptr()) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
+    /// Store `value` under a well-known configuration `key` in the database.
+    ///
+    /// Requires the database to be opened in read-write mode, and notmuch 0.32 or later.
+    #[cfg(notmuch_0_32)]
+    pub fn set_config_key(&self, key: ConfigKey, value: &str) -> Result<(), Status> {
+        let value = CString::new(value).unwrap();
+
+        let st = unsafe { notmuch_config_set(self.ptr, key.into(), value.as_ptr()) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
+    /// Store `values` under a list-valued configuration `key` such as `new.tags`.
+    ///
+    /// Requires notmuch 0.32 or later.
+    #[cfg(notmuch_0_32)]
+    pub fn set_config_values(&self, key: ConfigKey, values: &[&str]) -> Result<(), Status> {
+        self.set_config_key(key, &values.join(";"))
+    }
+
+    /// Create a new, empty database at `path`, opened in read-write mode.
+    ///
+    /// `path` is the mail root, which must already exist; the database is stored in its
+    /// `.notmuch` directory. Fails if a database already exists there.
+    pub fn create(path: &Path) -> Result<Self, Status> {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let mut db = std::ptr::null_mut();
+
+        let st = unsafe { notmuch_database_create(path.as_ptr(), &mut db) }.into();
+
+        match st {
+            Status::Success => Ok(Database::from(db)),
+            _ => Err(st),
+        }
+    }
+
+    /// Look up the directory record for `path`.
+    ///
+    /// `path` is either relative to the database path or an absolute path within it. Returns
+    /// `None` if the directory is not in the database; records are created when indexing the
+    /// first message within a directory.
+    pub fn directory(&self, path: &Path) -> Result<Option<Directory<'_>>, Status> {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let mut dir = std::ptr::null_mut();
+
+        let st =
+            unsafe { notmuch_database_get_directory(self.ptr, path.as_ptr(), &mut dir) }.into();
+
+        match st {
+            Status::Success if dir.is_null() => Ok(None),
+            Status::Success => Ok(Some(Directory::from(dir))),
+            _ => Err(st),
+        }
+    }
+
+    /// End an atomic database operation started with `begin_atomic`.
+    pub fn end_atomic(&self) -> Result<(), Status> {
+        let st = unsafe { notmuch_database_end_atomic(self.ptr) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
+    /// Find the message with the given `message_id`.
+    ///
+    /// Returns `None` if no such message is in the database.
+    pub fn find_message(&self, message_id: &str) -> Result<Option<Message<'_>>, Status> {
+        let message_id = CString::new(message_id).unwrap();
+        let mut msg = std::ptr::null_mut();
+
+        let st = unsafe { notmuch_database_find_message(self.ptr, message_id.as_ptr(), &mut msg) }
+            .into();
+
+        match st {
+            Status::Success if msg.is_null() => Ok(None),
+            Status::Success => Ok(Some(Message::owned(msg))),
+            _ => Err(st),
+        }
+    }
+
+    /// Find the message stored in the file at `path`.
+    ///
+    /// Returns `None` if the file is not in the database.
+    pub fn find_message_by_filename(&self, path: &Path) -> Result<Option<Message<'_>>, Status> {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let mut msg = std::ptr::null_mut();
+
+        let st =
+            unsafe { notmuch_database_find_message_by_filename(self.ptr, path.as_ptr(), &mut msg) }
+                .into();
+
+        match st {
+            Status::Success if msg.is_null() => Ok(None),
+            Status::Success => Ok(Some(Message::owned(msg))),
+            _ => Err(st),
+        }
+    }
+
+    /// Returns the default options for indexing messages, as set in the configuration.
+    ///
+    /// Changing the returned options doesn't change the configuration.
+    pub fn default_index_options(&self) -> Result<IndexOptions<'_>, Status> {
+        let ptr = unsafe { notmuch_database_get_default_indexopts(self.ptr) };
+
+        match ptr.is_null() {
+            true => Err(Status::OutOfMemory),
+            false => Ok(IndexOptions::from(ptr)),
+        }
+    }
+
+    /// Add the mail file at `path` to the database, using the default indexing options.
+    ///
+    /// `path` is either relative to the database path or an absolute path within it. If a message
+    /// with the same message ID already exists, the file is added to that message instead and the
+    /// returned flag is `true`.
+    pub fn index_file(&self, path: &Path) -> Result<(Message<'_>, bool), Status> {
+        self.index_file_with(path, std::ptr::null_mut())
+    }
+
+    /// Add the mail file at `path` to the database like `index_file`, using `options`.
+    ///
+    /// Fails with one of the crypto variants of `IndexError` if an encrypted or signed part
+    /// couldn't be processed according to the decryption policy.
+    pub fn index_file_with_options(
+        &self,
+        path: &Path,
+        options: &IndexOptions,
+    ) -> Result<(Message<'_>, bool), IndexError> {
+        Ok(self.index_file_with(path, options.into())?)
+    }
+
+    fn index_file_with(
+        &self,
+        path: &Path,
+        options: *mut notmuch_indexopts_t,
+    ) -> Result<(Message<'_>, bool), Status> {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let mut msg = std::ptr::null_mut();
+
+        let st = unsafe { notmuch_database_index_file(self.ptr, path.as_ptr(), options, &mut msg) }
+            .into();
+
+        match st {
+            Status::Success => Ok((Message::owned(msg), false)),
+            Status::DuplicateMessageId => Ok((Message::owned(msg), true)),
+            _ => Err(st),
+        }
+    }
+
+    /// Whether the database uses an older format that has to be upgraded with `upgrade`.
+    ///
+    /// Operations relying on newer features fail with `Status::UpgradeRequired` until then. This
+    /// is always `false` for databases opened in read-only mode, as they can't be upgraded.
+    pub fn needs_upgrade(&self) -> bool {
+        unsafe { notmuch_database_needs_upgrade(self.ptr) != 0 }
+    }
+
     /// Open a database at the provided `path` in either read-only or read-write mode.
     ///
     /// This is equivalent to opening the database without a configuration file.
@@ -49,6 +493,308 @@ impl Database {
             _ => Err(st),
         }
     }
+
+    /// Returns the path of the database, i.e. the directory containing `.notmuch`.
+    pub fn path(&self) -> &Path {
+        let path = unsafe { CStr::from_ptr(notmuch_database_get_path(self.ptr)) };
+        Path::new(OsStr::from_bytes(path.to_bytes()))
+    }
+
+    /// Open a database like `open`, waiting for the write lock if another writer holds it.
+    ///
+    /// Opening in read-write mode fails while another process, such as `notmuch new`, is writing
+    /// to the database. Instead of failing with `Status::XapianException`, this retries as
+    /// configured by `retry` and only fails with `OpenError::DatabaseLocked` once its timeout has
+    /// passed. Unlike `open`, errors are not printed to `stderr`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let retry = LockRetry {
+    ///     timeout: Duration::from_secs(60),
+    ///     ..LockRetry::default()
+    /// };
+    /// let db = Database::open_with_retry(&path, OpenMode::ReadWrite, &retry)?;
+    /// ```
+    ///
+    /// Requires notmuch 0.32 or later.
+    #[cfg(notmuch_0_32)]
+    pub fn open_with_retry(
+        path: &Path,
+        mode: OpenMode,
+        retry: &LockRetry,
+    ) -> Result<Self, OpenError> {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let start = Instant::now();
+        let mut delay = retry.initial_delay;
+
+        loop {
+            match Self::try_open(&path, mode) {
+                Err(OpenError::DatabaseLocked) => {
+                    let remaining = retry.timeout.saturating_sub(start.elapsed());
+
+                    if remaining.is_zero() {
+                        return Err(OpenError::DatabaseLocked);
+                    }
+
+                    thread::sleep(delay.min(remaining));
+                    delay = (delay * 2).min(retry.max_delay);
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Open the database once, recognizing a write lock held by someone else.
+    #[cfg(notmuch_0_32)]
+    fn try_open(path: &CStr, mode: OpenMode) -> Result<Self, OpenError> {
+        let mut db = std::ptr::null_mut();
+        let mut error_message = std::ptr::null_mut();
+
+        // An empty configuration path opens the database without a configuration file, like
+        // `notmuch_database_open` does.
+        let st = unsafe {
+            notmuch_database_open_with_config(
+                path.as_ptr(),
+                mode.into(),
+                "\0".as_ptr() as *const c_char,
+                std::ptr::null(),
+                &mut db,
+                &mut error_message,
+            )
+        }
+        .into();
+
+        let locked = !error_message.is_null()
+            && unsafe { CStr::from_ptr(error_message) }
+                .to_string_lossy()
+                .contains(WRITE_LOCK_ERROR);
+
+        unsafe { libc::free(error_message as *mut c_void) };
+
+        match st {
+            Status::Success => Ok(Database::from(db)),
+            Status::XapianException if locked => Err(OpenError::DatabaseLocked),
+            _ => Err(OpenError::Status(st)),
+        }
+    }
+
+    /// Remove the mail file at `path` from the database.
+    ///
+    /// The message itself is only removed along with its last file. Returns `true` if that was
+    /// the case, and `false` if the message is still available through other files.
+    pub fn remove_message(&self, path: &Path) -> Result<bool, Status> {
+        let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+
+        let st = unsafe { notmuch_database_remove_message(self.ptr, path.as_ptr()) }.into();
+
+        match st {
+            Status::Success => Ok(true),
+            Status::DuplicateMessageId => Ok(false),
+            _ => Err(st),
+        }
+    }
+
+    /// Reindex all messages matching the query string `query` using `options`, like
+    /// `notmuch reindex`.
+    ///
+    /// Messages are reindexed in batches, each in its own atomic section, so a crash leaves every
+    /// batch either fully reindexed or not at all. If reindexing a message fails, the messages
+    /// before it stay reindexed. `progress` is called after each batch with the number of
+    /// messages reindexed so far and the total. Returns that total.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let options = db.default_index_options()?;
+    /// options.set_decrypt_policy(DecryptPolicy::True)?;
+    ///
+    /// db.reindex_query("tag:encrypted", &options, |done, total| {
+    ///     println!("{}/{}", done, total)
+    /// })?;
+    /// ```
+    pub fn reindex_query<F>(
+        &self,
+        query: &str,
+        options: &IndexOptions,
+        mut progress: F,
+    ) -> Result<usize, IndexError>
+    where
+        F: FnMut(usize, usize),
+    {
+        // Reindexing changes the documents a running search would iterate over, so look the
+        // messages up again by ID.
+        let ids: Vec<String> = Query::create(self, query)
+            .search_messages()?
+            .into_iter()
+            .map(|msg| msg.message_id())
+            .collect();
+
+        for (i, batch) in ids.chunks(REINDEX_BATCH_SIZE).enumerate() {
+            self.atomic(|| self.reindex_batch(batch, options))?;
+
+            progress(i * REINDEX_BATCH_SIZE + batch.len(), ids.len());
+        }
+
+        Ok(ids.len())
+    }
+
+    fn reindex_batch(&self, ids: &[String], options: &IndexOptions) -> Result<(), IndexError> {
+        for id in ids {
+            // The message may have been removed in the meantime.
+            if let Some(msg) = self.find_message(id)? {
+                msg.reindex(options)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reopen the database in the given `mode`.
+    ///
+    /// Reopening in read-only mode picks up changes committed by other processes since the
+    /// database was opened. This allows holding a read-only handle and only taking the write lock
+    /// while writing:
+    ///
+    /// ```ignore
+    /// let mut db = Database::open(&path, OpenMode::ReadOnly)?;
+    ///
+    /// db.reopen(OpenMode::ReadWrite)?;
+    /// if let Some(msg) = db.find_message(&id)? {
+    ///     msg.add_tag("processed")?;
+    /// }
+    /// db.reopen(OpenMode::ReadOnly)?;
+    /// ```
+    ///
+    /// Messages, queries and other objects retrieved before reopening borrow the database, so
+    /// they have to be dropped first.
+    ///
+    /// Requires notmuch 0.32 or later.
+    #[cfg(notmuch_0_32)]
+    pub fn reopen(&mut self, mode: OpenMode) -> Result<(), Status> {
+        let st = unsafe { notmuch_database_reopen(self.ptr, mode.into()) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
+    /// Returns the committed database revision and the database UUID.
+    ///
+    /// Every message change is assigned a revision greater than the one committed before it, so
+    /// a saved revision can be used to find later changes with a `lastmod:` query. Revisions are
+    /// only comparable between databases with the same UUID; a different UUID means the database
+    /// was rebuilt.
+    pub fn revision(&self) -> (u64, String) {
+        let mut uuid = std::ptr::null();
+
+        let revision = unsafe { notmuch_database_get_revision(self.ptr, &mut uuid) };
+        let uuid = unsafe { CStr::from_ptr(uuid) }
+            .to_string_lossy()
+            .into_owned();
+
+        (revision as u64, uuid)
+    }
+
+    /// Returns the format version of the database.
+    ///
+    /// This is the version of the on-disk format, which is independent of the libnotmuch version.
+    pub fn version(&self) -> u32 {
+        unsafe { notmuch_database_get_version(self.ptr) as u32 }
+    }
+
+    /// Whether `capability` is available with this database.
+    ///
+    /// libnotmuch doesn't expose the feature flags of the database, so this checks the format
+    /// version instead where the capability depends on the database, see
+    /// `Capability::available_with`.
+    pub fn has_feature(&self, capability: Capability) -> bool {
+        capability.available_with(self.version())
+    }
+
+    /// Upgrade the database to the latest format supported by libnotmuch.
+    ///
+    /// `progress` is called periodically with the fraction of work done so far, between `0.0`
+    /// and `1.0`. This is a no-op if `needs_upgrade` returns `false`. Requires the database to be
+    /// opened in read-write mode.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let db = Database::open(&path, OpenMode::ReadWrite)?;
+    ///
+    /// if db.needs_upgrade() {
+    ///     db.upgrade(|progress| eprint!("\rUpgrading: {:.0}%", progress * 100.0))?;
+    /// }
+    /// ```
+    pub fn upgrade<F>(&self, progress: F) -> Result<(), Status>
+    where
+        F: FnMut(f64),
+    {
+        let mut callback = Callback::new(progress);
+
+        let st = unsafe {
+            notmuch_database_upgrade(
+                self.ptr,
+                Some(upgrade_progress::<F>),
+                &mut callback as *mut Callback<F> as *mut c_void,
+            )
+        }
+        .into();
+
+        callback.resume_panic();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+}
+
+/// Closure passed to libnotmuch as a callback.
+///
+/// Unwinding into C aborts the process, so a panic of the closure is caught and resumed once
+/// libnotmuch returns. The closure isn't called again after panicking.
+struct Callback<F> {
+    closure: F,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl<F> Callback<F> {
+    fn new(closure: F) -> Self {
+        Callback {
+            closure,
+            panic: None,
+        }
+    }
+
+    fn call(&mut self, call: impl FnOnce(&mut F)) {
+        if self.panic.is_some() {
+            return;
+        }
+
+        let closure = &mut self.closure;
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| call(closure))) {
+            self.panic = Some(payload);
+        }
+    }
+
+    fn resume_panic(self) {
+        if let Some(payload) = self.panic {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+unsafe extern "C" fn compact_status<F: FnMut(&str)>(message: *const c_char, closure: *mut c_void) {
+    let callback = &mut *(closure as *mut Callback<F>);
+    callback.call(|progress| progress(&CStr::from_ptr(message).to_string_lossy()));
+}
+
+unsafe extern "C" fn upgrade_progress<F: FnMut(f64)>(closure: *mut c_void, fraction: f64) {
+    let callback = &mut *(closure as *mut Callback<F>);
+    callback.call(|progress| progress(fraction));
 }
 
 impl Drop for Database {
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+use crate::{filenames::Filenames, status::Status};
+use notmuch_sys::{
+    notmuch_directory_delete, notmuch_directory_destroy, notmuch_directory_get_child_directories,
+    notmuch_directory_get_child_files, notmuch_directory_get_mtime, notmuch_directory_set_mtime,
+    notmuch_directory_t,
+};
+use std::marker::PhantomData;
+
+/// A directory known to the database.
+///
+/// The database stores the modification time of each directory when it was last scanned for
+/// mail, which allows skipping directories that have not changed since.
+pub struct Directory<'a> {
+    ptr: *mut notmuch_directory_t,
+    _covariant: PhantomData<&'a ()>,
+}
+
+impl Directory<'_> {
+    /// Returns a `Filenames` iterator over the names of the sub-directories of this directory.
+    ///
+    /// The names are relative to this directory.
+    pub fn child_directories(&self) -> Option<Filenames<'_>> {
+        Filenames::from(unsafe { notmuch_directory_get_child_directories(self.ptr) })
+    }
+
+    /// Returns a `Filenames` iterator over the names of the message files in this directory.
+    ///
+    /// The names are relative to this directory.
+    pub fn child_files(&self) -> Option<Filenames<'_>> {
+        Filenames::from(unsafe { notmuch_directory_get_child_files(self.ptr) })
+    }
+
+    /// Remove the directory from the database.
+    ///
+    /// Any files and sub-directories within it must have been removed beforehand.
+    pub fn delete(self) -> Result<(), Status> {
+        let ptr = self.ptr;
+
+        // `notmuch_directory_delete` also destroys the directory.
+        std::mem::forget(self);
+
+        let st = unsafe { notmuch_directory_delete(ptr) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
+    /// Modification time stored with `set_mtime`, in seconds since the epoch.
+    ///
+    /// Returns 0 if no modification time was stored yet.
+    pub fn mtime(&self) -> i64 {
+        unsafe { notmuch_directory_get_mtime(self.ptr) as i64 }
+    }
+
+    /// Store the modification time of the directory, in seconds since the epoch.
+    ///
+    /// This should only be called once every file in the directory has been indexed.
+    pub fn set_mtime(&self, mtime: i64) -> Result<(), Status> {
+        let st = unsafe { notmuch_directory_set_mtime(self.ptr, mtime as _) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+}
+
+impl Drop for Directory<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            notmuch_directory_destroy(self.ptr);
+        }
+    }
+}
+
+impl From<*mut notmuch_directory_t> for Directory<'_> {
+    fn from(ptr: *mut notmuch_directory_t) -> Self {
+        Directory {
+            ptr,
+            _covariant: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Escaping used by the `notmuch dump` and `notmuch restore` batch formats.
+
+/// Characters `notmuch dump` leaves unescaped.
+const SAFE_CHARS: &[u8] = b"+-_@=.,";
+
+/// Escape `s` the way `notmuch dump` does, replacing unsafe bytes with `%xx`.
+pub(crate) fn hex_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || SAFE_CHARS.contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02x}", b));
+        }
+    }
+
+    encoded
+}
+
+/// Reverse `hex_encode`, returning `None` on a malformed escape sequence.
+pub(crate) fn hex_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            // `from_str_radix` alone would also accept a sign, as in `%+a`.
+            let hex = s
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Structured notmuch queries.
+//!
+//! A `QueryExpr` renders to notmuch query syntax with every value quoted as needed, so message
+//! IDs, tags or folder names containing spaces, quotes or parentheses can't change the meaning
+//! of the query.
+//!
+//! # Example
+//!
+//! ```
+//! use notmuch_rs::expr::QueryExpr;
+//!
+//! let expr = QueryExpr::tag("inbox")
+//!     .and(!QueryExpr::tag("spam"))
+//!     .and(QueryExpr::folder("Sent Items").or(QueryExpr::from("\"Doe\", John")));
+//!
+//! assert_eq!(
+//!     r#"tag:inbox and not tag:spam and (folder:"Sent Items" or from:"""Doe"", John")"#,
+//!     expr.to_string(),
+//! );
+//! ```
+use std::{fmt::Display, ops::Not};
+
+/// Prefix restricting a term to one field of the messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Prefix {
+    /// Words in the message body.
+    Body,
+    /// Sender of the message.
+    From,
+    /// Recipients of the message.
+    To,
+    /// Words in the subject.
+    Subject,
+    /// Name of an attachment.
+    Attachment,
+    /// MIME type of a message part.
+    Mimetype,
+    /// Tag of the message.
+    Tag,
+    /// Message ID, without the enclosing angle brackets.
+    Id,
+    /// Thread ID.
+    Thread,
+    /// Maildir folder relative to the mail root, e.g. `work/INBOX`.
+    Folder,
+    /// Directory relative to the mail root.
+    Path,
+    /// Message property, as `key=value`.
+    Property,
+    /// Message date.
+    Date,
+    /// Revision of the last modification.
+    Lastmod,
+    /// Named query, saved as a `query.<name>` configuration key.
+    Query,
+}
+
+impl Prefix {
+    /// Returns the name of the prefix in query syntax, without the trailing colon.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Prefix::Body => "body",
+            Prefix::From => "from",
+            Prefix::To => "to",
+            Prefix::Subject => "subject",
+            Prefix::Attachment => "attachment",
+            Prefix::Mimetype => "mimetype",
+            Prefix::Tag => "tag",
+            Prefix::Id => "id",
+            Prefix::Thread => "thread",
+            Prefix::Folder => "folder",
+            Prefix::Path => "path",
+            Prefix::Property => "property",
+            Prefix::Date => "date",
+            Prefix::Lastmod => "lastmod",
+            Prefix::Query => "query",
+        }
+    }
+}
+
+/// Expression in the notmuch query language.
+///
+/// Use `to_string` to get the query string to pass to `Query::create`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryExpr {
+    /// Matches every message.
+    All,
+    /// Free text, searched for in the body, subject and headers.
+    ///
+    /// Text with several words is searched for as a phrase.
+    Text(String),
+    /// Value of a prefixed field.
+    Term(Prefix, String),
+    /// Range of a `Date` or `Lastmod` field. A missing bound leaves that side of the range open.
+    Range(Prefix, Option<String>, Option<String>),
+    /// Value of a header indexed under a user-defined prefix, configured as
+    /// `index.header.<prefix>`.
+    Header(String, String),
+    /// Messages in threads containing a message matched by the expression.
+    ThreadOf(Box<QueryExpr>),
+    /// Query text passed through as is, for syntax not modeled here such as wildcards or `near`.
+    Raw(String),
+    /// Matches messages matched by every expression; matches everything if empty.
+    And(Vec<QueryExpr>),
+    /// Matches messages matched by any expression; matches nothing if empty.
+    Or(Vec<QueryExpr>),
+    /// Matches messages not matched by the expression.
+    Not(Box<QueryExpr>),
+}
+
+impl QueryExpr {
+    /// Free text, searched for in the body, subject and headers.
+    pub fn text(text: &str) -> Self {
+        QueryExpr::Text(text.to_string())
+    }
+
+    /// Messages tagged with `tag`.
+    pub fn tag(tag: &str) -> Self {
+        QueryExpr::Term(Prefix::Tag, tag.to_string())
+    }
+
+    /// Messages sent by `from`, an address or name.
+    pub fn from(from: &str) -> Self {
+        QueryExpr::Term(Prefix::From, from.to_string())
+    }
+
+    /// Messages sent to `to`, an address or name.
+    pub fn to(to: &str) -> Self {
+        QueryExpr::Term(Prefix::To, to.to_string())
+    }
+
+    /// Messages whose subject contains `subject`.
+    pub fn subject(subject: &str) -> Self {
+        QueryExpr::Term(Prefix::Subject, subject.to_string())
+    }
+
+    /// The message with the given `message_id`.
+    pub fn id(message_id: &str) -> Self {
+        QueryExpr::Term(Prefix::Id, message_id.to_string())
+    }
+
+    /// Messages in the thread with the given `thread_id`.
+    pub fn thread(thread_id: &str) -> Self {
+        QueryExpr::Term(Prefix::Thread, thread_id.to_string())
+    }
+
+    /// Messages with a file in the maildir `folder`, relative to the mail root.
+    pub fn folder(folder: &str) -> Self {
+        QueryExpr::Term(Prefix::Folder, folder.to_string())
+    }
+
+    /// Messages with a file in the directory `path`, relative to the mail root.
+    pub fn path(path: &str) -> Self {
+        QueryExpr::Term(Prefix::Path, path.to_string())
+    }
+
+    /// Messages whose header indexed under the user-defined `prefix` contains `value`.
+    pub fn header(prefix: &str, value: &str) -> Self {
+        QueryExpr::Header(prefix.to_string(), value.to_string())
+    }
+
+    /// Messages matched by the named query `name`.
+    pub fn named(name: &str) -> Self {
+        QueryExpr::Term(Prefix::Query, name.to_string())
+    }
+
+    /// Messages in threads containing a message matched by `expr`.
+    pub fn thread_of(expr: QueryExpr) -> Self {
+        QueryExpr::ThreadOf(Box::new(expr))
+    }
+
+    /// Messages with the property `key` set to `value`.
+    pub fn property(key: &str, value: &str) -> Self {
+        QueryExpr::Term(Prefix::Property, format!("{}={}", key, value))
+    }
+
+    /// Messages dated between `since` and `until`, in any format understood by notmuch, e.g.
+    /// `2021-06-01` or `yesterday`.
+    pub fn date(since: Option<&str>, until: Option<&str>) -> Self {
+        QueryExpr::Range(
+            Prefix::Date,
+            since.map(str::to_string),
+            until.map(str::to_string),
+        )
+    }
+
+    /// Messages last modified at a revision between `since` and `until`, inclusive.
+    pub fn lastmod(since: Option<u64>, until: Option<u64>) -> Self {
+        QueryExpr::Range(
+            Prefix::Lastmod,
+            since.map(|rev| rev.to_string()),
+            until.map(|rev| rev.to_string()),
+        )
+    }
+
+    /// Messages matched by both this expression and `other`.
+    pub fn and(self, other: QueryExpr) -> Self {
+        match self {
+            QueryExpr::And(mut exprs) => {
+                exprs.push(other);
+                QueryExpr::And(exprs)
+            }
+            _ => QueryExpr::And(vec![self, other]),
+        }
+    }
+
+    /// Messages matched by either this expression or `other`.
+    pub fn or(self, other: QueryExpr) -> Self {
+        match self {
+            QueryExpr::Or(mut exprs) => {
+                exprs.push(other);
+                QueryExpr::Or(exprs)
+            }
+            _ => QueryExpr::Or(vec![self, other]),
+        }
+    }
+
+    /// Render the expression in S-expression query syntax.
+    ///
+    /// The result is meant for `Query::create_with_syntax` with `QuerySyntax::Sexp`.
+    ///
+    /// ```
+    /// use notmuch_rs::expr::QueryExpr;
+    ///
+    /// let expr = QueryExpr::tag("inbox").and(!QueryExpr::from("Doe, John"));
+    /// assert_eq!(r#"(and (tag inbox) (not (from "Doe, John")))"#, expr.to_sexp());
+    /// ```
+    pub fn to_sexp(&self) -> String {
+        match self {
+            QueryExpr::All => "()".to_string(),
+            QueryExpr::Text(text) => quote_sexp(text),
+            QueryExpr::Term(prefix, value) => {
+                format!("({} {})", prefix.as_str(), quote_sexp(value))
+            }
+            QueryExpr::Range(prefix, since, until) => format!(
+                "({} {} {})",
+                prefix.as_str(),
+                since.as_deref().map_or("*".to_string(), quote_sexp),
+                until.as_deref().map_or("*".to_string(), quote_sexp),
+            ),
+            QueryExpr::Header(prefix, value) => format!("({} {})", prefix, quote_sexp(value)),
+            QueryExpr::ThreadOf(expr) => format!("(thread (of {}))", expr.to_sexp()),
+            QueryExpr::Raw(query) => format!("(infix {})", quote_sexp(query)),
+            QueryExpr::And(exprs) if exprs.is_empty() => "()".to_string(),
+            QueryExpr::Or(exprs) if exprs.is_empty() => "(not ())".to_string(),
+            QueryExpr::And(exprs) | QueryExpr::Or(exprs) if exprs.len() == 1 => exprs[0].to_sexp(),
+            QueryExpr::And(exprs) => format!("(and {})", sexp_list(exprs)),
+            QueryExpr::Or(exprs) => format!("(or {})", sexp_list(exprs)),
+            QueryExpr::Not(expr) => format!("(not {})", expr.to_sexp()),
+        }
+    }
+
+    /// Whether rendering this expression as an operand requires parentheses.
+    fn is_compound(&self) -> bool {
+        match self {
+            QueryExpr::And(exprs) | QueryExpr::Or(exprs) => exprs.len() != 1,
+            QueryExpr::Raw(query) => query.contains(char::is_whitespace),
+            _ => false,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_compound() {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl Not for QueryExpr {
+    type Output = QueryExpr;
+
+    fn not(self) -> Self::Output {
+        QueryExpr::Not(Box::new(self))
+    }
+}
+
+impl Display for QueryExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryExpr::All => write!(f, "*"),
+            QueryExpr::Text(text) => write!(f, "{}", quote(text)),
+            QueryExpr::Term(prefix, value) => write!(f, "{}:{}", prefix.as_str(), quote(value)),
+            QueryExpr::Range(prefix, since, until) => write!(
+                f,
+                "{}:{}..{}",
+                prefix.as_str(),
+                since.as_deref().map(quote).unwrap_or_default(),
+                until.as_deref().map(quote).unwrap_or_default(),
+            ),
+            QueryExpr::Header(prefix, value) => write!(f, "{}:{}", prefix, quote(value)),
+            QueryExpr::ThreadOf(expr) => write!(f, "thread:{{{}}}", expr),
+            QueryExpr::Raw(query) => write!(f, "{}", query),
+            QueryExpr::And(exprs) | QueryExpr::Or(exprs) if exprs.len() == 1 => exprs[0].fmt(f),
+            QueryExpr::And(exprs) if exprs.is_empty() => write!(f, "*"),
+            QueryExpr::Or(exprs) if exprs.is_empty() => write!(f, "not *"),
+            QueryExpr::And(exprs) | QueryExpr::Or(exprs) => {
+                let op = match self {
+                    QueryExpr::And(_) => " and ",
+                    _ => " or ",
+                };
+
+                for (i, expr) in exprs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", op)?;
+                    }
+                    expr.fmt_operand(f)?;
+                }
+
+                Ok(())
+            }
+            QueryExpr::Not(expr) => {
+                write!(f, "not ")?;
+                expr.fmt_operand(f)
+            }
+        }
+    }
+}
+
+/// Operators of the query language, which are only taken literally when quoted.
+const OPERATORS: [&str; 6] = ["and", "or", "not", "xor", "near", "adj"];
+
+/// Quote `value` as a single term if it would otherwise be split or interpreted.
+///
+/// Within quotes, a double quote is written as two double quotes.
+pub(crate) fn quote(value: &str) -> String {
+    let needs_quoting = value.is_empty()
+        || value.starts_with(['-', '+'])
+        || value.contains("..")
+        || OPERATORS.iter().any(|op| value.eq_ignore_ascii_case(op))
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || "()\":*".contains(c) || !c.is_ascii());
+
+    if needs_quoting {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn sexp_list(exprs: &[QueryExpr]) -> String {
+    exprs
+        .iter()
+        .map(QueryExpr::to_sexp)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote `value` as a single S-expression atom if it would otherwise be split or interpreted.
+///
+/// Within quotes, double quotes and backslashes are escaped with a backslash.
+fn quote_sexp(value: &str) -> String {
+    let needs_quoting = value.is_empty()
+        || value.starts_with(':')
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || "()\"\\'*".contains(c));
+
+    if needs_quoting {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+use notmuch_sys::{
+    notmuch_filenames_destroy, notmuch_filenames_get, notmuch_filenames_move_to_next,
+    notmuch_filenames_t, notmuch_filenames_valid,
+};
+use std::{ffi::CStr, ffi::OsStr, marker::PhantomData, os::unix::ffi::OsStrExt, path::PathBuf};
+
+/// Iterator over file or directory names stored in the database.
+pub struct Filenames<'a> {
+    ptr: *mut notmuch_filenames_t,
+    _covariant: PhantomData<&'a ()>,
+}
+
+impl Filenames<'_> {
+    /// Creates a `Filenames` iterator from a given raw `ptr`
+    ///
+    /// If `ptr` is NULL, this returns None.
+    pub fn from(ptr: *mut notmuch_filenames_t) -> Option<Self> {
+        if ptr.is_null() {
+            return None;
+        }
+
+        Some(Filenames {
+            ptr,
+            _covariant: PhantomData,
+        })
+    }
+}
+
+impl Drop for Filenames<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            notmuch_filenames_destroy(self.ptr);
+        }
+    }
+}
+
+impl Iterator for Filenames<'_> {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if unsafe { notmuch_filenames_valid(self.ptr) } == 0 {
+            return None;
+        }
+
+        let name = unsafe { CStr::from_ptr(notmuch_filenames_get(self.ptr)) };
+        let path = PathBuf::from(OsStr::from_bytes(name.to_bytes()));
+
+        unsafe { notmuch_filenames_move_to_next(self.ptr) }
+
+        Some(path)
+    }
+}
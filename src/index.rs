@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Options for indexing messages, such as whether to index the cleartext of encrypted messages.
+use crate::status::Status;
+use notmuch_sys::{
+    notmuch_decryption_policy_t, notmuch_decryption_policy_t_NOTMUCH_DECRYPT_AUTO,
+    notmuch_decryption_policy_t_NOTMUCH_DECRYPT_FALSE,
+    notmuch_decryption_policy_t_NOTMUCH_DECRYPT_NOSTASH,
+    notmuch_decryption_policy_t_NOTMUCH_DECRYPT_TRUE, notmuch_indexopts_destroy,
+    notmuch_indexopts_get_decrypt_policy, notmuch_indexopts_set_decrypt_policy,
+    notmuch_indexopts_t,
+};
+use std::{error::Error, fmt::Display, marker::PhantomData};
+
+/// Whether to decrypt encrypted messages when indexing them.
+///
+/// Indexing the cleartext makes encrypted messages searchable, but stores their contents in the
+/// database unencrypted. The database default is the `index.decrypt` configuration option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecryptPolicy {
+    /// Never decrypt.
+    False,
+    /// Decrypt with the user's keys, and stash the session key of each decrypted message as a
+    /// `session-key` property so it can be decrypted again without the keys.
+    True,
+    /// Only decrypt messages with a stashed session key. This is the notmuch default.
+    Auto,
+    /// Decrypt like `True`, but don't stash session keys.
+    NoStash,
+}
+
+impl From<DecryptPolicy> for notmuch_decryption_policy_t {
+    fn from(policy: DecryptPolicy) -> Self {
+        match policy {
+            DecryptPolicy::False => notmuch_decryption_policy_t_NOTMUCH_DECRYPT_FALSE,
+            DecryptPolicy::True => notmuch_decryption_policy_t_NOTMUCH_DECRYPT_TRUE,
+            DecryptPolicy::Auto => notmuch_decryption_policy_t_NOTMUCH_DECRYPT_AUTO,
+            DecryptPolicy::NoStash => notmuch_decryption_policy_t_NOTMUCH_DECRYPT_NOSTASH,
+        }
+    }
+}
+
+impl From<notmuch_decryption_policy_t> for DecryptPolicy {
+    fn from(policy: notmuch_decryption_policy_t) -> Self {
+        match policy {
+            notmuch_sys::notmuch_decryption_policy_t_NOTMUCH_DECRYPT_FALSE => DecryptPolicy::False,
+            notmuch_sys::notmuch_decryption_policy_t_NOTMUCH_DECRYPT_TRUE => DecryptPolicy::True,
+            notmuch_sys::notmuch_decryption_policy_t_NOTMUCH_DECRYPT_NOSTASH => {
+                DecryptPolicy::NoStash
+            }
+            _ => DecryptPolicy::Auto,
+        }
+    }
+}
+
+/// Options for indexing messages, see `Database::default_index_options`.
+#[derive(Debug)]
+pub struct IndexOptions<'a> {
+    ptr: *mut notmuch_indexopts_t,
+    _covariant: PhantomData<&'a ()>,
+}
+
+impl IndexOptions<'_> {
+    /// Returns the decryption policy.
+    pub fn decrypt_policy(&self) -> DecryptPolicy {
+        unsafe { notmuch_indexopts_get_decrypt_policy(self.ptr) }.into()
+    }
+
+    /// Set the decryption policy.
+    pub fn set_decrypt_policy(&self, policy: DecryptPolicy) -> Result<(), Status> {
+        let st = unsafe { notmuch_indexopts_set_decrypt_policy(self.ptr, policy.into()) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+}
+
+impl From<*mut notmuch_indexopts_t> for IndexOptions<'_> {
+    fn from(ptr: *mut notmuch_indexopts_t) -> Self {
+        IndexOptions {
+            ptr,
+            _covariant: PhantomData,
+        }
+    }
+}
+
+impl From<&IndexOptions<'_>> for *mut notmuch_indexopts_t {
+    fn from(options: &IndexOptions) -> Self {
+        options.ptr
+    }
+}
+
+impl Drop for IndexOptions<'_> {
+    fn drop(&mut self) {
+        unsafe { notmuch_indexopts_destroy(self.ptr) }
+    }
+}
+
+/// Error indexing a message.
+///
+/// Failures to process encrypted or signed parts get their own variants, as they can be worked
+/// around by indexing with `DecryptPolicy::False`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexError {
+    /// A part claims to be encrypted or signed, but its protocol is missing or garbled.
+    MalformedCryptoProtocol,
+    /// The crypto engine couldn't be initialized, e.g. because GnuPG isn't installed.
+    CryptoContextCreation,
+    /// A part is encrypted or signed with a protocol notmuch doesn't support.
+    UnknownCryptoProtocol,
+    /// Indexing failed for another reason.
+    Status(Status),
+}
+
+impl Error for IndexError {}
+
+impl Display for IndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexError::MalformedCryptoProtocol => write!(
+                f,
+                "Message has a malformed encrypted or signed part, \
+                 index it without decryption to skip it"
+            ),
+            IndexError::CryptoContextCreation => write!(
+                f,
+                "Unable to set up decryption, check that GnuPG is installed and gpg-agent can \
+                 be started, or index without decryption"
+            ),
+            IndexError::UnknownCryptoProtocol => write!(
+                f,
+                "Message is encrypted or signed with an unsupported protocol, \
+                 index it without decryption to skip it"
+            ),
+            IndexError::Status(st) => write!(f, "{}", st),
+        }
+    }
+}
+
+impl From<Status> for IndexError {
+    fn from(st: Status) -> Self {
+        match st {
+            Status::MalformedCryptoProtocol => IndexError::MalformedCryptoProtocol,
+            Status::FailedCryptoContextCreation => IndexError::CryptoContextCreation,
+            Status::UnknownCryptoProtocol => IndexError::UnknownCryptoProtocol,
+            _ => IndexError::Status(st),
+        }
+    }
+}
@@ -1,8 +1,29 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 pub use notmuch_sys as raw;
 
+pub mod config;
+pub mod crypto;
+pub mod date;
 pub mod db;
+pub mod directory;
+mod dump;
+pub mod expr;
+pub mod filenames;
+pub mod index;
 pub mod message;
+#[cfg(feature = "mime")]
+pub mod mime;
+#[cfg(notmuch_0_32)]
+pub mod new;
+pub mod parse;
+pub mod properties;
 pub mod query;
+#[cfg(all(feature = "mime", notmuch_0_32))]
+pub mod reply;
 pub mod status;
+pub mod sync;
 pub mod tags;
+pub mod thread;
+pub mod version;
+#[cfg(all(feature = "watch", notmuch_0_32))]
+pub mod watch;
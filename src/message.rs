@@ -1,21 +1,50 @@
-use crate::{status::Status, tags::Tags};
+use crate::{
+    index::{IndexError, IndexOptions},
+    properties::Properties,
+    status::Status,
+    tags::Tags,
+};
 use notmuch_sys::{
-    notmuch_message_add_tag, notmuch_message_freeze, notmuch_message_get_message_id,
-    notmuch_message_get_tags, notmuch_message_remove_tag, notmuch_message_t, notmuch_message_thaw,
-    notmuch_messages_destroy, notmuch_messages_get, notmuch_messages_move_to_next,
-    notmuch_messages_t, notmuch_messages_valid,
+    notmuch_message_add_property, notmuch_message_add_tag, notmuch_message_destroy,
+    notmuch_message_freeze, notmuch_message_get_filename, notmuch_message_get_message_id,
+    notmuch_message_get_properties, notmuch_message_get_property, notmuch_message_get_tags,
+    notmuch_message_maildir_flags_to_tags, notmuch_message_reindex,
+    notmuch_message_remove_all_properties, notmuch_message_remove_property,
+    notmuch_message_remove_tag, notmuch_message_t, notmuch_message_tags_to_maildir_flags,
+    notmuch_message_thaw, notmuch_messages_destroy, notmuch_messages_get,
+    notmuch_messages_move_to_next, notmuch_messages_t, notmuch_messages_valid,
 };
 use std::{
-    ffi::{CStr, CString},
+    ffi::{CStr, CString, OsStr},
     marker::PhantomData,
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
 };
 
 pub struct Message<'a> {
     msg: *mut notmuch_message_t,
+    /// Whether the message was looked up directly and must be destroyed by us.
+    owned: bool,
     _covariant: PhantomData<&'a ()>,
 }
 
 impl<'a> Message<'a> {
+    /// Add the property `key` with `value` to the message.
+    ///
+    /// A key may have several values. Adding a value that is already present has no effect.
+    pub fn add_property(&self, key: &str, value: &str) -> Result<(), Status> {
+        let key = CString::new(key).unwrap();
+        let value = CString::new(value).unwrap();
+
+        let st =
+            unsafe { notmuch_message_add_property(self.msg, key.as_ptr(), value.as_ptr()) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
     /// Add a given `tag` to the message.
     pub fn add_tag(&self, tag: &str) -> Result<(), Status> {
         let tag = CString::new(tag).unwrap();
@@ -28,6 +57,20 @@ impl<'a> Message<'a> {
         }
     }
 
+    /// Returns the path of a file containing the message.
+    ///
+    /// If the message is stored in several files, which one is returned is arbitrary. Returns
+    /// `None` if libnotmuch fails to look it up, e.g. after the database has been closed.
+    pub fn filename(&self) -> Option<PathBuf> {
+        let raw_str = unsafe { notmuch_message_get_filename(self.msg) };
+
+        if raw_str.is_null() {
+            return None;
+        }
+
+        Some(OsStr::from_bytes(unsafe { CStr::from_ptr(raw_str) }.to_bytes()).into())
+    }
+
     /// Freeze the current state of `message` within the database.
     pub fn freeze(&self) -> Result<(), Status> {
         let st = unsafe { notmuch_message_freeze(self.msg) }.into();
@@ -38,12 +81,37 @@ impl<'a> Message<'a> {
         }
     }
 
+    /// Run `f` with the message frozen, thawing it even if `f` fails.
+    pub(crate) fn frozen<T, F>(&self, f: F) -> Result<T, Status>
+    where
+        F: FnOnce() -> Result<T, Status>,
+    {
+        self.freeze()?;
+        let res = f();
+        let thaw = self.thaw();
+
+        let value = res?;
+        thaw?;
+
+        Ok(value)
+    }
+
     /// Returns a `Tags` iterator for all tags associated with this message.
-    pub fn get_tags(&self) -> Option<Tags> {
+    pub fn get_tags(&self) -> Option<Tags<'_>> {
         let tags_ptr = unsafe { notmuch_message_get_tags(self.msg) };
         Tags::from(tags_ptr)
     }
 
+    /// Add or remove tags of the message according to the maildir flags in its filenames.
+    pub fn maildir_flags_to_tags(&self) -> Result<(), Status> {
+        let st = unsafe { notmuch_message_maildir_flags_to_tags(self.msg) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
     /// Get message id from notmuch.
     pub fn message_id(&self) -> String {
         let raw_str = unsafe { notmuch_message_get_message_id(self.msg) };
@@ -54,6 +122,78 @@ impl<'a> Message<'a> {
             .to_string()
     }
 
+    /// Returns a `Properties` iterator over the message's properties with the key `key`, or
+    /// with keys starting with `key` if `exact` is `false`.
+    pub fn properties(&self, key: &str, exact: bool) -> Option<Properties<'_>> {
+        let key = CString::new(key).unwrap();
+
+        let ptr = unsafe { notmuch_message_get_properties(self.msg, key.as_ptr(), exact as i32) };
+        Properties::from(ptr)
+    }
+
+    /// Returns a value of the property `key`, or `None` if the message doesn't have it.
+    ///
+    /// If the key has several values, any one of them is returned.
+    pub fn property(&self, key: &str) -> Result<Option<String>, Status> {
+        let key = CString::new(key).unwrap();
+        let mut value = std::ptr::null();
+
+        let st = unsafe { notmuch_message_get_property(self.msg, key.as_ptr(), &mut value) }.into();
+
+        match st {
+            Status::Success if value.is_null() => Ok(None),
+            Status::Success => Ok(Some(
+                unsafe { CStr::from_ptr(value) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )),
+            _ => Err(st),
+        }
+    }
+
+    /// Index the message's files again using `options`, e.g. to index the cleartext of encrypted
+    /// messages after changing the decryption policy.
+    ///
+    /// Tags and properties are kept, except for properties set by indexing itself, such as
+    /// `index.decryption`.
+    pub fn reindex(&self, options: &IndexOptions) -> Result<(), IndexError> {
+        let st = unsafe { notmuch_message_reindex(self.msg, options.into()) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st.into()),
+        }
+    }
+
+    /// Remove all values of the property `key`, or all properties if `key` is `None`.
+    pub fn remove_all_properties(&self, key: Option<&str>) -> Result<(), Status> {
+        let key = key.map(|key| CString::new(key).unwrap());
+        let key_ptr = key.as_ref().map_or(std::ptr::null(), |key| key.as_ptr());
+
+        let st = unsafe { notmuch_message_remove_all_properties(self.msg, key_ptr) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
+    /// Remove `value` from the property `key`.
+    ///
+    /// Removing a value that isn't present has no effect.
+    pub fn remove_property(&self, key: &str, value: &str) -> Result<(), Status> {
+        let key = CString::new(key).unwrap();
+        let value = CString::new(value).unwrap();
+
+        let st = unsafe { notmuch_message_remove_property(self.msg, key.as_ptr(), value.as_ptr()) }
+            .into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
     /// Remove a given `tag` from the message.
     pub fn remove_tag(&self, tag: &str) -> Result<(), Status> {
         let tag = CString::new(tag).unwrap();
@@ -66,6 +206,18 @@ impl<'a> Message<'a> {
         }
     }
 
+    /// Rename the message's files so their maildir flags match its tags.
+    ///
+    /// Only files within a maildir `cur` or `new` directory are renamed.
+    pub fn tags_to_maildir_flags(&self) -> Result<(), Status> {
+        let st = unsafe { notmuch_message_tags_to_maildir_flags(self.msg) }.into();
+
+        match st {
+            Status::Success => Ok(()),
+            _ => Err(st),
+        }
+    }
+
     /// Thaw the current `message`, synchronizing any changes that may have occurred while
     /// `message` was frozen into the notmuch database.
     pub fn thaw(&self) -> Result<(), Status> {
@@ -78,10 +230,32 @@ impl<'a> Message<'a> {
     }
 }
 
+impl Message<'_> {
+    /// Wrap a message looked up directly in the database, destroying it once dropped.
+    pub(crate) fn owned(ptr: *mut notmuch_message_t) -> Self {
+        Message {
+            msg: ptr,
+            owned: true,
+            _covariant: PhantomData,
+        }
+    }
+}
+
+impl Drop for Message<'_> {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe {
+                notmuch_message_destroy(self.msg);
+            }
+        }
+    }
+}
+
 impl From<*mut notmuch_message_t> for Message<'_> {
     fn from(ptr: *mut notmuch_message_t) -> Self {
         Message {
             msg: ptr,
+            owned: false,
             _covariant: PhantomData,
         }
     }
@@ -103,9 +277,20 @@ impl From<*mut notmuch_message_t> for Message<'_> {
 /// ```
 pub struct Messages<'a> {
     ptr: *mut notmuch_messages_t,
+    offset: usize,
+    limit: Option<usize>,
     _covariant: PhantomData<&'a ()>,
 }
 
+impl Messages<'_> {
+    /// Restrict iteration to `limit` messages after skipping the first `offset`.
+    pub(crate) fn window(mut self, offset: usize, limit: Option<usize>) -> Self {
+        self.offset = offset;
+        self.limit = limit;
+        self
+    }
+}
+
 impl Drop for Messages<'_> {
     fn drop(&mut self) {
         unsafe {
@@ -118,6 +303,8 @@ impl From<*mut notmuch_messages_t> for Messages<'_> {
     fn from(ptr: *mut notmuch_messages_t) -> Self {
         Messages {
             ptr,
+            offset: 0,
+            limit: None,
             _covariant: PhantomData,
         }
     }
@@ -128,29 +315,60 @@ impl<'a> IntoIterator for &Messages<'a> {
     type IntoIter = MessagesIter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        MessagesIter::from_ptr(self.ptr)
+        MessagesIter::from_ptr(self.ptr, self.offset, self.limit)
     }
 }
 
 // NOTE: This intentionally does not implement `Drop`. The pointer is owned by a `Messages` struct.
 pub struct MessagesIter<'a> {
     ptr: *mut notmuch_messages_t,
+    skip: usize,
+    remaining: Option<usize>,
     _covariant: PhantomData<&'a ()>,
 }
 
 impl MessagesIter<'_> {
-    fn from_ptr(ptr: *mut notmuch_messages_t) -> Self {
+    fn from_ptr(ptr: *mut notmuch_messages_t, skip: usize, remaining: Option<usize>) -> Self {
         MessagesIter {
             ptr,
+            skip,
+            remaining,
             _covariant: PhantomData,
         }
     }
+
+    /// Skip the next `n` messages without constructing a `Message` for them.
+    ///
+    /// Unlike `Iterator::skip`, this never loads the skipped messages from the database.
+    pub fn skip_raw(mut self, n: usize) -> Self {
+        self.skip += n;
+        self
+    }
+
+    /// Whether any messages are left to iterate over, ignoring the limit.
+    pub(crate) fn has_next(&mut self) -> bool {
+        self.skip_pending();
+        unsafe { notmuch_messages_valid(self.ptr) != 0 }
+    }
+
+    fn skip_pending(&mut self) {
+        while self.skip > 0 && unsafe { notmuch_messages_valid(self.ptr) != 0 } {
+            unsafe { notmuch_messages_move_to_next(self.ptr) }
+            self.skip -= 1;
+        }
+    }
 }
 
 impl<'a> Iterator for MessagesIter<'a> {
     type Item = Message<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        self.skip_pending();
+
+        if self.remaining == Some(0) {
+            return None;
+        }
+
         let is_valid = unsafe { notmuch_messages_valid(self.ptr) != 0 };
 
         if !is_valid {
@@ -163,6 +381,10 @@ impl<'a> Iterator for MessagesIter<'a> {
         // Advance the pointer
         unsafe { notmuch_messages_move_to_next(self.ptr) }
 
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= 1;
+        }
+
         Some(cur)
     }
 }
@@ -0,0 +1,368 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! MIME structure of messages.
+//!
+//! Parts are numbered like `notmuch show --part=N`: part 0 is the whole message, part 1 its body
+//! and the remaining parts are numbered depth-first in the order they appear. The message
+//! embedded in a `message/rfc822` part `N` has its body numbered `N + 1`.
+//!
+//! This module requires the `mime` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let root = msg.parts()?;
+//!
+//! for part in root.iter().filter(|part| part.children.is_empty()) {
+//!     println!("{}: {}", part.number, part.content_type);
+//! }
+//!
+//! for attachment in msg.attachments()? {
+//!     if let Some(name) = attachment.filename {
+//!         msg.write_part(attachment.number, File::create(name)?)?;
+//!     }
+//! }
+//! ```
+use crate::message::Message;
+use mailparse::{body::Body, DispositionType, MailHeaderMap, MailParseError, ParsedMail};
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::Display,
+    fs,
+    io::{self, Write},
+};
+
+/// Content type of the whole message, part 0.
+const MESSAGE_CONTENT_TYPE: &str = "message/rfc822";
+
+/// Value of the `Content-Disposition` header of a part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Disposition {
+    /// The part is meant to be displayed as part of the message.
+    Inline,
+    /// The part is meant to be saved rather than displayed.
+    Attachment,
+    /// Any other disposition, in lower case.
+    Other(String),
+}
+
+impl From<DispositionType> for Disposition {
+    fn from(disposition: DispositionType) -> Self {
+        match disposition {
+            DispositionType::Inline => Disposition::Inline,
+            DispositionType::Attachment => Disposition::Attachment,
+            DispositionType::FormData => Disposition::Other("form-data".to_string()),
+            DispositionType::Extension(other) => Disposition::Other(other),
+        }
+    }
+}
+
+/// A MIME part of a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    /// Number of the part, as used by `notmuch show --part`.
+    pub number: usize,
+    /// Lower case MIME type, e.g. `text/plain`.
+    pub content_type: String,
+    /// Charset parameter of the content type, if given.
+    pub charset: Option<String>,
+    /// Disposition of the part, if it has a `Content-Disposition` header.
+    pub disposition: Option<Disposition>,
+    /// Suggested filename from the disposition, or else the `name` parameter of the content type.
+    pub filename: Option<String>,
+    /// Content of the part, as written by `notmuch show --format=raw --part`.
+    ///
+    /// For leaf parts, this is the body with the content transfer encoding decoded, but in its
+    /// original charset. For multipart and message parts, this is the raw part including its
+    /// headers.
+    pub body: Vec<u8>,
+    /// Sub-parts of a multipart part, or the body of an embedded message.
+    pub children: Vec<Part>,
+}
+
+impl Part {
+    /// Returns the attachments in this tree, ordered by number.
+    pub fn attachments(&self) -> Vec<Attachment> {
+        self.iter()
+            .filter(|part| part.is_attachment())
+            .map(|part| Attachment {
+                number: part.number,
+                filename: part.filename.clone(),
+                content_type: part.content_type.clone(),
+                size: part.body.len(),
+            })
+            .collect()
+    }
+
+    /// Returns the part numbered `number` in this tree.
+    pub fn find(&self, number: usize) -> Option<&Part> {
+        self.iter().find(|part| part.number == number)
+    }
+
+    /// Whether the part is an attachment, i.e. has an `attachment` disposition.
+    ///
+    /// These are the parts for which notmuch adds the `attachment` tag when indexing.
+    pub fn is_attachment(&self) -> bool {
+        self.disposition == Some(Disposition::Attachment)
+    }
+
+    /// Returns an iterator over this part and all its descendants, ordered by number.
+    pub fn iter(&self) -> PartsIter<'_> {
+        PartsIter { stack: vec![self] }
+    }
+}
+
+/// Summary of an attachment part, see `Part::attachments`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// Number of the part, as used by `Message::write_part`.
+    pub number: usize,
+    /// Suggested filename, if the sender gave one.
+    pub filename: Option<String>,
+    /// Lower case MIME type, e.g. `application/pdf`.
+    pub content_type: String,
+    /// Size of the decoded content in bytes.
+    pub size: usize,
+}
+
+/// Depth-first iterator over a tree of parts, see `Part::iter`.
+pub struct PartsIter<'a> {
+    stack: Vec<&'a Part>,
+}
+
+impl<'a> Iterator for PartsIter<'a> {
+    type Item = &'a Part;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let part = self.stack.pop()?;
+        self.stack.extend(part.children.iter().rev());
+
+        Some(part)
+    }
+}
+
+/// Error reading the MIME structure of a message.
+#[derive(Debug)]
+pub enum MimeError {
+    /// The message file couldn't be read.
+    Io(io::Error),
+    /// The message couldn't be parsed.
+    Parse(String),
+    /// The message has no part with the given number.
+    NoSuchPart(usize),
+    /// The file of the message couldn't be looked up in the database.
+    NoFilename,
+}
+
+impl Error for MimeError {}
+
+impl Display for MimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MimeError::Io(e) => write!(f, "{}", e),
+            MimeError::Parse(e) => write!(f, "Malformed message: {}", e),
+            MimeError::NoSuchPart(number) => write!(f, "Message has no part {}", number),
+            MimeError::NoFilename => write!(f, "Unable to look up the file of the message"),
+        }
+    }
+}
+
+impl From<io::Error> for MimeError {
+    fn from(e: io::Error) -> Self {
+        MimeError::Io(e)
+    }
+}
+
+impl From<MailParseError> for MimeError {
+    fn from(e: MailParseError) -> Self {
+        MimeError::Parse(e.to_string())
+    }
+}
+
+impl Message<'_> {
+    /// Returns the MIME structure of the message, read from its file.
+    ///
+    /// The returned part is part 0, the whole message.
+    pub fn parts(&self) -> Result<Part, MimeError> {
+        let filename = self.filename().ok_or(MimeError::NoFilename)?;
+
+        parse(&fs::read(filename)?)
+    }
+
+    /// Returns the attachments of the message, read from its file.
+    pub fn attachments(&self) -> Result<Vec<Attachment>, MimeError> {
+        Ok(self.parts()?.attachments())
+    }
+
+    /// Write the content of part `number` to `writer`, like
+    /// `notmuch show --format=raw --part=number`, see `mime::write_part`.
+    pub fn write_part<W: Write>(&self, number: usize, writer: W) -> Result<(), MimeError> {
+        let filename = self.filename().ok_or(MimeError::NoFilename)?;
+
+        write_part(&fs::read(filename)?, number, writer)
+    }
+}
+
+/// Parse the MIME structure of the raw message `raw`.
+///
+/// The returned part is part 0, the whole message.
+pub fn parse(raw: &[u8]) -> Result<Part, MimeError> {
+    let mail = mailparse::parse_mail(raw)?;
+    let mut next = 1;
+
+    Ok(Part {
+        number: 0,
+        content_type: MESSAGE_CONTENT_TYPE.to_string(),
+        charset: None,
+        disposition: None,
+        filename: None,
+        body: raw.to_vec(),
+        children: vec![convert(&mail, &mut next, false)?],
+    })
+}
+
+/// Write the content of part `number` of the raw message `raw` to `writer`, like
+/// `notmuch show --format=raw --part=number`.
+///
+/// Leaf parts, such as attachments, are written with their content transfer encoding decoded.
+/// Multipart and message parts are written as is, including their headers.
+///
+/// Unlike `parse`, this only decodes the requested part, and the bodies of the embedded messages
+/// it is in. That part is still decoded into memory as a whole before being written.
+pub fn write_part<W: Write>(raw: &[u8], number: usize, mut writer: W) -> Result<(), MimeError> {
+    if number == 0 {
+        writer.write_all(raw)?;
+    } else {
+        let mail = mailparse::parse_mail(raw)?;
+        let mut next = 1;
+
+        if !write_descendant(&mail, number, &mut next, false, &mut writer)? {
+            return Err(MimeError::NoSuchPart(number));
+        }
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Write part `number` to `writer` if it is `mail` or one of its descendants, numbered starting
+/// at `next`, returning whether it was found.
+fn write_descendant<W: Write>(
+    mail: &ParsedMail,
+    number: usize,
+    next: &mut usize,
+    in_multipart: bool,
+    writer: &mut W,
+) -> Result<bool, MimeError> {
+    let current = *next;
+    *next += 1;
+
+    if current == number {
+        writer.write_all(&content(mail, in_multipart)?)?;
+        return Ok(true);
+    }
+
+    if mail.ctype.mimetype.starts_with("multipart/") {
+        for sub in &mail.subparts {
+            if write_descendant(sub, number, next, true, writer)? {
+                return Ok(true);
+            }
+        }
+    } else if mail.ctype.mimetype == MESSAGE_CONTENT_TYPE {
+        let embedded = embedded(mail, in_multipart)?;
+
+        return write_descendant(
+            &mailparse::parse_mail(&embedded)?,
+            number,
+            next,
+            false,
+            writer,
+        );
+    }
+
+    Ok(false)
+}
+
+/// Convert `mail` and its descendants to parts, numbering them starting at `next`.
+///
+/// The line break before a boundary belongs to the boundary, so it is dropped from the parts of
+/// a multipart, which mailparse leaves it in.
+fn convert(mail: &ParsedMail, next: &mut usize, in_multipart: bool) -> Result<Part, MimeError> {
+    let number = *next;
+    *next += 1;
+
+    let content_type = mail.ctype.mimetype.clone();
+    let mut children = Vec::new();
+
+    if content_type.starts_with("multipart/") {
+        for sub in &mail.subparts {
+            children.push(convert(sub, next, true)?);
+        }
+    } else if content_type == MESSAGE_CONTENT_TYPE {
+        let embedded = embedded(mail, in_multipart)?;
+        children.push(convert(&mailparse::parse_mail(&embedded)?, next, false)?);
+    }
+
+    let body = content(mail, in_multipart)?.into_owned();
+
+    let disposition = mail
+        .headers
+        .get_first_value("Content-Disposition")
+        .map(|_| mail.get_content_disposition());
+    let filename = disposition
+        .as_ref()
+        .and_then(|disposition| disposition.params.get("filename"))
+        .or_else(|| mail.ctype.params.get("name"))
+        .cloned();
+
+    Ok(Part {
+        number,
+        content_type,
+        charset: mail.ctype.params.get("charset").cloned(),
+        disposition: disposition.map(|disposition| disposition.disposition.into()),
+        filename,
+        body,
+        children,
+    })
+}
+
+/// Returns the content of `mail` as written by `notmuch show --format=raw --part`, see
+/// `Part::body`.
+fn content<'a>(mail: &ParsedMail<'a>, in_multipart: bool) -> Result<Cow<'a, [u8]>, MimeError> {
+    let content_type = mail.ctype.mimetype.as_str();
+
+    if content_type.starts_with("multipart/") || content_type == MESSAGE_CONTENT_TYPE {
+        return Ok(Cow::Borrowed(if in_multipart {
+            strip_line_break(mail.raw_bytes)
+        } else {
+            mail.raw_bytes
+        }));
+    }
+
+    let mut body = mail.get_body_raw()?;
+    // Base64 ignores line breaks, so the decoded body doesn't contain it.
+    if in_multipart && !matches!(mail.get_body_encoded(), Body::Base64(_)) {
+        body.truncate(strip_line_break(&body).len());
+    }
+
+    Ok(Cow::Owned(body))
+}
+
+/// Returns the raw message embedded in the `message/rfc822` part `mail`.
+fn embedded(mail: &ParsedMail, in_multipart: bool) -> Result<Vec<u8>, MimeError> {
+    let mut embedded = mail.get_body_raw()?;
+    if in_multipart {
+        embedded.truncate(strip_line_break(&embedded).len());
+    }
+
+    Ok(embedded)
+}
+
+/// Returns `bytes` without one trailing line break.
+pub(crate) fn strip_line_break(bytes: &[u8]) -> &[u8] {
+    bytes
+        .strip_suffix(b"\r\n")
+        .or_else(|| bytes.strip_suffix(b"\n"))
+        .unwrap_or(bytes)
+}
@@ -0,0 +1,349 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Native implementation of `notmuch new`.
+//!
+//! Walks the mail root, indexes new files, removes vanished ones and detects renames. Directory
+//! modification times are stored in the database, so unchanged directories are not rescanned.
+//!
+//! This module requires notmuch 0.32 or later, as it reads the well-known configuration keys.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let db = Database::open(&path, OpenMode::ReadWrite)?;
+//! let summary = new::run(&db)?;
+//! println!("added {} messages", summary.added);
+//! ```
+use crate::{config::ConfigKey, db::Database, status::Status};
+use regex::Regex;
+use std::{
+    collections::{BTreeSet, HashSet},
+    ffi::{OsStr, OsString},
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Counts of the changes made by `run`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NewSummary {
+    /// Messages added to the database.
+    pub added: usize,
+    /// Messages removed from the database along with their last file.
+    pub removed: usize,
+    /// Messages that lost a file but are still available through another, e.g. after being
+    /// moved to a different folder.
+    pub renamed: usize,
+    /// Files and directories that couldn't be indexed, removed or read, which were skipped.
+    pub errors: Vec<(PathBuf, Status)>,
+}
+
+/// Entry of `new.ignore`.
+enum Ignore {
+    /// A file or directory name, ignored anywhere in the mail root.
+    Name(OsString),
+    /// A regular expression matched against the path relative to the mail root.
+    Regex(Regex),
+}
+
+impl Ignore {
+    fn parse(entry: &str) -> Result<Self, Status> {
+        match entry
+            .strip_prefix('/')
+            .and_then(|entry| entry.strip_suffix('/'))
+        {
+            Some(re) => Regex::new(re)
+                .map(Ignore::Regex)
+                .map_err(|_| Status::IllegalArgument),
+            None => Ok(Ignore::Name(OsString::from(entry))),
+        }
+    }
+
+    fn matches(&self, name: &OsStr, relative: &Path) -> bool {
+        match self {
+            Ignore::Name(ignored) => ignored == name,
+            Ignore::Regex(re) => matches!(relative.to_str(), Some(path) if re.is_match(path)),
+        }
+    }
+}
+
+/// Applies the `notmuch new` configuration to individual files and directories.
+pub(crate) struct Scanner<'a> {
+    db: &'a Database,
+    pub(crate) mail_root: PathBuf,
+    new_tags: Vec<String>,
+    ignore: Vec<Ignore>,
+    sync_flags: bool,
+    summary: NewSummary,
+    /// Files that vanished, removed only after all new files were added to detect renames.
+    removed_files: Vec<PathBuf>,
+    removed_dirs: Vec<PathBuf>,
+    /// Device and inode numbers of the directories scanned, to not follow symlinks in circles.
+    visited: HashSet<(u64, u64)>,
+}
+
+/// Scan the mail root configured in `database.mail_root` and update the database accordingly.
+///
+/// This honors `new.tags`, `new.ignore` and `maildir.synchronize_flags`. Each added or removed
+/// file is handled within its own atomic section. The database must be opened in read-write
+/// mode.
+///
+/// Files and directories that fail are listed in `NewSummary::errors` and the scan goes on.
+/// Errors affecting the whole database, or reading the mail root itself, stop the scan.
+pub fn run(db: &Database) -> Result<NewSummary, Status> {
+    let mut scanner = Scanner::new(db)?;
+    let mail_root = scanner.mail_root.clone();
+
+    scanner.scan_dir(&mail_root)?;
+
+    for path in std::mem::take(&mut scanner.removed_files) {
+        if let Err(st) = scanner.remove_file(&path) {
+            scanner.skip(&path, st)?;
+        }
+    }
+
+    for path in std::mem::take(&mut scanner.removed_dirs) {
+        if let Err(st) = scanner.remove_dir(&path) {
+            scanner.skip(&path, st)?;
+        }
+    }
+
+    Ok(scanner.summary)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+impl<'a> Scanner<'a> {
+    /// Load the `notmuch new` configuration of `db`.
+    pub(crate) fn new(db: &'a Database) -> Result<Self, Status> {
+        let mail_root = db
+            .config_key(ConfigKey::MailRoot)
+            .map(PathBuf::from)
+            .ok_or(Status::PathError)?;
+        let new_tags = db
+            .config_values(ConfigKey::NewTags)
+            .map(|values| values.filter(|tag| !tag.is_empty()).collect())
+            .unwrap_or_default();
+        let ignore = db
+            .config_values(ConfigKey::NewIgnore)
+            .map(|values| {
+                values
+                    .filter(|entry| !entry.is_empty())
+                    .map(|entry| Ignore::parse(&entry))
+                    .collect::<Result<_, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let sync_flags = db.config_bool(ConfigKey::SyncMaildirFlags)?;
+
+        Ok(Scanner {
+            db,
+            mail_root,
+            new_tags,
+            ignore,
+            sync_flags,
+            summary: NewSummary::default(),
+            removed_files: Vec::new(),
+            removed_dirs: Vec::new(),
+            visited: HashSet::new(),
+        })
+    }
+
+    /// Whether the file or directory `name` at `path` is excluded by `new.ignore`.
+    pub(crate) fn is_ignored(&self, name: &OsStr, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.mail_root).unwrap_or(path);
+        self.ignore
+            .iter()
+            .any(|ignore| ignore.matches(name, relative))
+    }
+
+    /// Record that the file or directory at `path` failed with `st` and was skipped, unless `st`
+    /// affects the whole database.
+    fn skip(&mut self, path: &Path, st: Status) -> Result<(), Status> {
+        match st {
+            Status::OutOfMemory
+            | Status::ReadOnlyDatabase
+            | Status::XapianException
+            | Status::UpgradeRequired => Err(st),
+            _ => {
+                self.summary.errors.push((path.to_path_buf(), st));
+                Ok(())
+            }
+        }
+    }
+
+    fn scan_dir(&mut self, path: &Path) -> Result<(), Status> {
+        let scan_time = now();
+        let metadata = fs::metadata(path).map_err(|_| Status::FileError)?;
+        let fs_mtime = metadata.mtime();
+
+        // Symlinks may lead back to a directory that is already being scanned.
+        if !self.visited.insert((metadata.dev(), metadata.ino())) {
+            return Ok(());
+        }
+
+        let mut fs_files = BTreeSet::new();
+        let mut fs_dirs = BTreeSet::new();
+
+        for entry in fs::read_dir(path).map_err(|_| Status::FileError)? {
+            let entry = entry.map_err(|_| Status::FileError)?;
+            let name = entry.file_name();
+            let entry_path = entry.path();
+
+            if name == ".notmuch" || self.is_ignored(&name, &entry_path) {
+                continue;
+            }
+
+            // Follow symlinks, as notmuch does.
+            let metadata = match fs::metadata(&entry_path) {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+
+            if metadata.is_dir() {
+                fs_dirs.insert(name);
+            } else if metadata.is_file() {
+                fs_files.insert(name);
+            }
+        }
+
+        // Mail is only ever delivered to `tmp` temporarily.
+        if fs_dirs.contains(&OsString::from("cur")) && fs_dirs.contains(&OsString::from("new")) {
+            fs_dirs.remove(&OsString::from("tmp"));
+        }
+
+        for name in &fs_dirs {
+            let dir = path.join(name);
+            if let Err(st) = self.scan_dir(&dir) {
+                self.skip(&dir, st)?;
+            }
+        }
+
+        let (db_files, db_dirs, db_mtime) = match self.db.directory(path)? {
+            Some(dir) => (
+                dir.child_files()
+                    .map(|files| files.map(PathBuf::into_os_string).collect())
+                    .unwrap_or_default(),
+                dir.child_directories()
+                    .map(|dirs| dirs.map(PathBuf::into_os_string).collect())
+                    .unwrap_or_default(),
+                dir.mtime(),
+            ),
+            None => (BTreeSet::new(), BTreeSet::new(), 0),
+        };
+
+        if db_mtime == fs_mtime {
+            return Ok(());
+        }
+
+        let errors = self.summary.errors.len();
+
+        for name in fs_files.difference(&db_files) {
+            let file = path.join(name);
+            if let Err(st) = self.add_file(&file) {
+                self.skip(&file, st)?;
+            }
+        }
+
+        for name in db_files.difference(&fs_files) {
+            self.removed_files.push(path.join(name));
+        }
+
+        for name in db_dirs.difference(&fs_dirs) {
+            self.removed_dirs.push(path.join(name));
+        }
+
+        // A directory modified within the current second may still change without its mtime
+        // changing, so it has to be scanned again next time. Its record was created along with
+        // the first message indexed within it or below it; libnotmuch can't create records for
+        // directories without any mail, so those are rescanned every time, as with `notmuch new`.
+        // Files that failed are retried next time as well.
+        if fs_mtime < scan_time && self.summary.errors.len() == errors {
+            if let Some(dir) = self.db.directory(path)? {
+                dir.set_mtime(fs_mtime)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Index the file at `path`, returning whether it was a mail file.
+    pub(crate) fn add_file(&mut self, path: &Path) -> Result<bool, Status> {
+        let db = self.db;
+
+        db.atomic(|| match db.index_file(path) {
+            Ok((msg, false)) => {
+                msg.frozen(|| {
+                    for tag in &self.new_tags {
+                        msg.add_tag(tag)?;
+                    }
+                    if self.sync_flags {
+                        msg.maildir_flags_to_tags()?;
+                    }
+                    Ok(())
+                })?;
+
+                self.summary.added += 1;
+                Ok(true)
+            }
+            Ok((msg, true)) => {
+                if self.sync_flags {
+                    msg.maildir_flags_to_tags()?;
+                }
+                Ok(true)
+            }
+            // Non-mail files in the mail root are skipped, like `notmuch new` does.
+            Err(Status::FileNotEmail) => Ok(false),
+            Err(st) => Err(st),
+        })
+    }
+
+    /// Remove the file at `path` from the database.
+    pub(crate) fn remove_file(&mut self, path: &Path) -> Result<(), Status> {
+        let db = self.db;
+
+        db.atomic(|| {
+            let msg = db.find_message_by_filename(path)?;
+
+            if db.remove_message(path)? {
+                self.summary.removed += 1;
+            } else {
+                self.summary.renamed += 1;
+
+                // The remaining files may carry different maildir flags.
+                if let (true, Some(msg)) = (self.sync_flags, msg) {
+                    msg.maildir_flags_to_tags()?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    fn remove_dir(&mut self, path: &Path) -> Result<(), Status> {
+        let dir = match self.db.directory(path)? {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+
+        let files: Vec<PathBuf> = dir.child_files().map(Iterator::collect).unwrap_or_default();
+        let dirs: Vec<PathBuf> = dir
+            .child_directories()
+            .map(Iterator::collect)
+            .unwrap_or_default();
+
+        for name in files {
+            self.remove_file(&path.join(name))?;
+        }
+
+        for name in dirs {
+            self.remove_dir(&path.join(name))?;
+        }
+
+        dir.delete()
+    }
+}
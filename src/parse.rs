@@ -0,0 +1,684 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Parser for the notmuch query language.
+//!
+//! Parsing happens entirely in Rust, without a database, so queries can be validated while they
+//! are being written. Errors carry the byte range of the offending part of the query.
+//!
+//! Like in notmuch, a word with a prefix that isn't known is free text. Prefixes defined with
+//! `index.header.<prefix>` must be passed to `parse_with_headers` to be recognized.
+//!
+//! # Example
+//!
+//! ```
+//! use notmuch_rs::{expr::QueryExpr, parse};
+//!
+//! let expr = parse::parse("tag:inbox and not from:alice@example").unwrap();
+//! assert_eq!(
+//!     QueryExpr::tag("inbox").and(!QueryExpr::from("alice@example")),
+//!     expr
+//! );
+//!
+//! let err = parse::parse("tag:inbox and (folder:work").unwrap_err();
+//! assert_eq!(14..15, err.span);
+//! ```
+use crate::expr::{Prefix, QueryExpr};
+use std::{
+    error::Error,
+    fmt::Display,
+    iter::Peekable,
+    ops::Range,
+    str::{CharIndices, FromStr},
+};
+
+/// Kind of error found while parsing a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The query ended where an expression was expected.
+    UnexpectedEnd,
+    /// A token that can't appear at this position, e.g. a closing parenthesis or an operator.
+    UnexpectedToken(String),
+    /// An opening parenthesis without a matching closing one.
+    UnclosedParen,
+    /// A double quote without a matching closing one.
+    UnclosedQuote,
+    /// An opening brace of a `thread:{...}` subquery without a matching closing one.
+    UnclosedBrace,
+    /// A prefix without a value, e.g. `tag:`.
+    MissingValue,
+    /// A malformed range, or a range on a prefix that doesn't support one.
+    InvalidRange(String),
+    /// Syntax that can't be represented, such as a wildcard within a term.
+    Unsupported(String),
+}
+
+/// Error found while parsing a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Byte range of the query the error refers to.
+    pub span: Range<usize>,
+}
+
+impl Error for ParseError {}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of query")?,
+            ParseErrorKind::UnexpectedToken(token) => write!(f, "unexpected `{}`", token)?,
+            ParseErrorKind::UnclosedParen => write!(f, "unclosed parenthesis")?,
+            ParseErrorKind::UnclosedQuote => write!(f, "unclosed quote")?,
+            ParseErrorKind::UnclosedBrace => write!(f, "unclosed brace")?,
+            ParseErrorKind::MissingValue => write!(f, "missing value after prefix")?,
+            ParseErrorKind::InvalidRange(msg) => write!(f, "invalid range: {}", msg)?,
+            ParseErrorKind::Unsupported(what) => write!(f, "{} is not supported", what)?,
+        }
+
+        write!(f, " at {}..{}", self.span.start, self.span.end)
+    }
+}
+
+impl FromStr for QueryExpr {
+    type Err = ParseError;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        parse(query)
+    }
+}
+
+/// Parse `query` into a `QueryExpr`.
+///
+/// An empty query matches every message, like it does in notmuch.
+pub fn parse(query: &str) -> Result<QueryExpr, ParseError> {
+    parse_with_headers(query, &[])
+}
+
+/// Parse `query` into a `QueryExpr`, recognizing the user-defined prefixes in `headers`.
+///
+/// `headers` are the names configured as `index.header.<prefix>`, e.g. `List` for
+/// `index.header.List=List-Id`.
+pub fn parse_with_headers(query: &str, headers: &[&str]) -> Result<QueryExpr, ParseError> {
+    parse_at(query, 0, headers)
+}
+
+/// Parse `query`, which starts at byte `offset` of the full query the spans refer to.
+fn parse_at(query: &str, offset: usize, headers: &[&str]) -> Result<QueryExpr, ParseError> {
+    let shift = |err: ParseError| ParseError {
+        span: err.span.start + offset..err.span.end + offset,
+        ..err
+    };
+
+    let tokens = lex(query).map_err(shift)?;
+
+    if tokens.is_empty() {
+        return Ok(QueryExpr::All);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        query,
+        headers,
+    };
+    let expr = parser.or_expr().map_err(shift)?;
+
+    match parser.peek() {
+        Some(token) => Err(shift(unexpected(token))),
+        None => Ok(expr),
+    }
+}
+
+/// Character of a word, with whether it was quoted.
+#[derive(Debug, Clone, Copy)]
+struct WordChar {
+    c: char,
+    pos: usize,
+    quoted: bool,
+}
+
+#[derive(Debug)]
+enum TokenKind {
+    LParen,
+    RParen,
+    Word(Vec<WordChar>),
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
+
+impl Token {
+    /// The binary or unary operator this token stands for, if any.
+    fn operator(&self) -> Option<String> {
+        let chars = match &self.kind {
+            TokenKind::Word(chars) => chars,
+            _ => return None,
+        };
+
+        if chars.iter().any(|c| c.quoted) {
+            return None;
+        }
+
+        let word: String = chars.iter().map(|c| c.c.to_ascii_lowercase()).collect();
+
+        match word.as_str() {
+            "and" | "or" | "not" | "xor" | "near" | "adj" => Some(word),
+            // `near` and `adj` optionally take the maximum distance, e.g. `near/3`.
+            _ => match word.split_once('/') {
+                Some(("near", distance)) | Some(("adj", distance))
+                    if !distance.is_empty() && distance.bytes().all(|b| b.is_ascii_digit()) =>
+                {
+                    Some(word)
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Whether this token is a `near` or `adj` operator.
+    fn is_proximity(&self) -> bool {
+        matches!(self.operator(), Some(op) if op.starts_with("near") || op.starts_with("adj"))
+    }
+
+    fn text(&self) -> String {
+        match &self.kind {
+            TokenKind::LParen => "(".to_string(),
+            TokenKind::RParen => ")".to_string(),
+            TokenKind::Word(chars) => chars.iter().map(|c| c.c).collect(),
+        }
+    }
+}
+
+fn unexpected(token: &Token) -> ParseError {
+    ParseError {
+        kind: ParseErrorKind::UnexpectedToken(token.text()),
+        span: token.span.clone(),
+    }
+}
+
+fn lex(query: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(Token {
+                kind: if c == '(' {
+                    TokenKind::LParen
+                } else {
+                    TokenKind::RParen
+                },
+                span: start..start + 1,
+            });
+            continue;
+        }
+
+        let mut word = Vec::new();
+        let mut end = start;
+
+        while let Some(&(pos, c)) = chars.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                break;
+            }
+
+            chars.next();
+            end = pos + c.len_utf8();
+
+            if c == '{' && is_thread_prefix(&word) {
+                word.push(WordChar {
+                    c,
+                    pos,
+                    quoted: false,
+                });
+                end = subquery(query, pos, &mut chars, &mut word)?;
+                continue;
+            }
+
+            if c != '"' {
+                word.push(WordChar {
+                    c,
+                    pos,
+                    quoted: false,
+                });
+                continue;
+            }
+
+            // Quoted part, where two double quotes stand for one.
+            loop {
+                match chars.next() {
+                    Some((pos, '"')) => {
+                        end = pos + 1;
+                        match chars.peek() {
+                            Some(&(pos, '"')) => {
+                                chars.next();
+                                word.push(WordChar {
+                                    c: '"',
+                                    pos,
+                                    quoted: true,
+                                });
+                            }
+                            _ => break,
+                        }
+                    }
+                    Some((pos, c)) => word.push(WordChar {
+                        c,
+                        pos,
+                        quoted: true,
+                    }),
+                    None => {
+                        return Err(ParseError {
+                            kind: ParseErrorKind::UnclosedQuote,
+                            span: pos..query.len(),
+                        })
+                    }
+                }
+            }
+        }
+
+        tokens.push(Token {
+            kind: TokenKind::Word(word),
+            span: start..end,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Whether `word` so far is the `thread:` prefix, which may be followed by a subquery in braces.
+fn is_thread_prefix(word: &[WordChar]) -> bool {
+    let word = match word.first() {
+        Some(WordChar {
+            c: '-' | '+',
+            quoted: false,
+            ..
+        }) => &word[1..],
+        _ => word,
+    };
+
+    !word.iter().any(|c| c.quoted) && text(word) == "thread:"
+}
+
+/// Read the subquery after the opening brace at `open` up to and including the closing brace,
+/// adding its characters to `word`.
+///
+/// The characters of the subquery are marked as quoted, so they are taken literally until the
+/// subquery is parsed on its own. Returns the end of the closing brace.
+fn subquery(
+    query: &str,
+    open: usize,
+    chars: &mut Peekable<CharIndices<'_>>,
+    word: &mut Vec<WordChar>,
+) -> Result<usize, ParseError> {
+    let mut in_quotes = false;
+
+    for (pos, c) in chars {
+        if c == '}' && !in_quotes {
+            word.push(WordChar {
+                c,
+                pos,
+                quoted: false,
+            });
+            return Ok(pos + 1);
+        }
+
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+
+        word.push(WordChar {
+            c,
+            pos,
+            quoted: true,
+        });
+    }
+
+    Err(ParseError {
+        kind: ParseErrorKind::UnclosedBrace,
+        span: open..query.len(),
+    })
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    query: &'a str,
+    /// User-defined prefixes.
+    headers: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_operator(&self) -> Option<String> {
+        self.peek().and_then(Token::operator)
+    }
+
+    fn peek_proximity(&self) -> bool {
+        matches!(self.peek(), Some(token) if token.is_proximity())
+    }
+
+    fn or_expr(&mut self) -> Result<QueryExpr, ParseError> {
+        let mut expr = self.xor_expr()?;
+
+        while self.peek_operator().as_deref() == Some("or") {
+            self.pos += 1;
+            expr = expr.or(self.xor_expr()?);
+        }
+
+        Ok(expr)
+    }
+
+    fn xor_expr(&mut self) -> Result<QueryExpr, ParseError> {
+        let mut expr = self.and_expr()?;
+
+        while self.peek_operator().as_deref() == Some("xor") {
+            self.pos += 1;
+            let other = self.and_expr()?;
+
+            // `QueryExpr` has no exclusive or, so spell it out.
+            expr = expr.clone().and(!other.clone()).or((!expr).and(other));
+        }
+
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<QueryExpr, ParseError> {
+        let mut exprs = vec![self.unary()?];
+
+        loop {
+            match self.peek_operator().as_deref() {
+                Some("and") => self.pos += 1,
+                Some("or") | Some("xor") => break,
+                // Adjacent expressions are implicitly combined with `and`.
+                Some(_) => (),
+                None => match self.peek().map(|token| &token.kind) {
+                    Some(TokenKind::Word(_)) | Some(TokenKind::LParen) => (),
+                    _ => break,
+                },
+            }
+
+            exprs.push(self.unary()?);
+        }
+
+        Ok(match exprs.len() {
+            1 => exprs.remove(0),
+            _ => QueryExpr::And(exprs),
+        })
+    }
+
+    fn unary(&mut self) -> Result<QueryExpr, ParseError> {
+        match self.peek_operator().as_deref() {
+            Some("not") => {
+                self.pos += 1;
+                Ok(!self.unary()?)
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<QueryExpr, ParseError> {
+        let token = match self.tokens.get(self.pos) {
+            Some(token) => token,
+            None => return Err(self.end()),
+        };
+
+        if token.operator().is_some() {
+            return Err(unexpected(token));
+        }
+
+        self.pos += 1;
+
+        match &token.kind {
+            TokenKind::LParen => {
+                let open = token.span.clone();
+                let expr = self.or_expr()?;
+
+                match self.peek() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    Some(token) => Err(unexpected(token)),
+                    None => Err(ParseError {
+                        kind: ParseErrorKind::UnclosedParen,
+                        span: open,
+                    }),
+                }
+            }
+            TokenKind::RParen => Err(unexpected(token)),
+            TokenKind::Word(chars) => {
+                let expr = term(chars, token.span.clone(), self.headers)?;
+
+                if self.peek_proximity() {
+                    self.proximity(token.span.start)
+                } else {
+                    Ok(expr)
+                }
+            }
+        }
+    }
+
+    /// Parse the rest of a chain of `near` or `adj` operators on the words starting at `start`.
+    ///
+    /// `QueryExpr` doesn't model them, so the chain is kept as it was written.
+    fn proximity(&mut self, start: usize) -> Result<QueryExpr, ParseError> {
+        let mut end = start;
+
+        while self.peek_proximity() {
+            self.pos += 1;
+
+            match self.peek() {
+                Some(token) if token.operator().is_none() => match token.kind {
+                    TokenKind::Word(_) => end = token.span.end,
+                    _ => return Err(unexpected(token)),
+                },
+                Some(token) => return Err(unexpected(token)),
+                None => return Err(self.end()),
+            }
+
+            self.pos += 1;
+        }
+
+        Ok(QueryExpr::Raw(self.query[start..end].to_string()))
+    }
+
+    fn end(&self) -> ParseError {
+        ParseError {
+            kind: ParseErrorKind::UnexpectedEnd,
+            span: self.query.len()..self.query.len(),
+        }
+    }
+}
+
+fn prefix_from_name(name: &str) -> Option<Prefix> {
+    let prefix = match name {
+        "body" => Prefix::Body,
+        "from" => Prefix::From,
+        "to" => Prefix::To,
+        "subject" => Prefix::Subject,
+        "attachment" => Prefix::Attachment,
+        "mimetype" => Prefix::Mimetype,
+        "tag" | "is" => Prefix::Tag,
+        "id" | "mid" => Prefix::Id,
+        "thread" => Prefix::Thread,
+        "folder" => Prefix::Folder,
+        "path" => Prefix::Path,
+        "property" => Prefix::Property,
+        "date" => Prefix::Date,
+        "lastmod" => Prefix::Lastmod,
+        "query" => Prefix::Query,
+        _ => return None,
+    };
+
+    Some(prefix)
+}
+
+fn text(chars: &[WordChar]) -> String {
+    chars.iter().map(|c| c.c).collect()
+}
+
+fn span_of(chars: &[WordChar], fallback: &Range<usize>) -> Range<usize> {
+    match (chars.first(), chars.last()) {
+        (Some(first), Some(last)) => first.pos..last.pos + last.c.len_utf8(),
+        _ => fallback.clone(),
+    }
+}
+
+/// Parse a single word, which may be prefixed, negated, a range or a wildcard.
+fn term(chars: &[WordChar], span: Range<usize>, headers: &[&str]) -> Result<QueryExpr, ParseError> {
+    match chars.first() {
+        Some(WordChar {
+            c: '-',
+            quoted: false,
+            ..
+        }) if chars.len() > 1 => return Ok(!term(&chars[1..], span, headers)?),
+        Some(WordChar {
+            c: '+',
+            quoted: false,
+            ..
+        }) if chars.len() > 1 => return term(&chars[1..], span, headers),
+        _ => (),
+    }
+
+    if chars.len() == 1 && chars[0].c == '*' && !chars[0].quoted {
+        return Ok(QueryExpr::All);
+    }
+
+    let colon = chars
+        .iter()
+        .position(|c| c.quoted || !c.c.is_ascii_alphabetic())
+        .filter(|&i| i > 0 && chars[i].c == ':' && !chars[i].quoted);
+
+    let name = colon.map(|i| text(&chars[..i]));
+    let header = name
+        .as_deref()
+        .filter(|name| headers.contains(name))
+        .map(str::to_string);
+    let prefix = name.as_deref().and_then(prefix_from_name);
+
+    // Words with an unknown prefix are free text, as they are for notmuch.
+    let value = match colon {
+        Some(i) if prefix.is_some() || header.is_some() => &chars[i + 1..],
+        _ => chars,
+    };
+
+    if value.is_empty() {
+        return Err(ParseError {
+            kind: ParseErrorKind::MissingValue,
+            span,
+        });
+    }
+
+    if prefix == Some(Prefix::Thread) && value[0].c == '{' && !value[0].quoted {
+        // The lexer marks everything up to the closing brace as quoted, so anything after it is
+        // extra.
+        let close = value[1..]
+            .iter()
+            .position(|c| !c.quoted)
+            .map_or(0, |i| i + 1);
+        if close + 1 < value.len() {
+            return Err(ParseError {
+                kind: ParseErrorKind::UnexpectedToken(text(&value[close + 1..])),
+                span: span_of(&value[close + 1..], &span),
+            });
+        }
+
+        let inner = &value[1..close];
+        let start = value[0].pos + 1;
+        return Ok(QueryExpr::thread_of(parse_at(
+            &text(inner),
+            start,
+            headers,
+        )?));
+    }
+
+    if let Some(star) = value.iter().position(|c| c.c == '*' && !c.quoted) {
+        // Only an unquoted term can end with a wildcard.
+        let is_wildcard = star == value.len() - 1
+            && !chars.iter().any(|c| c.quoted)
+            && !matches!(prefix, Some(Prefix::Date) | Some(Prefix::Lastmod));
+
+        if !is_wildcard {
+            return Err(ParseError {
+                kind: ParseErrorKind::Unsupported("wildcard within a term".to_string()),
+                span: value[star].pos..value[star].pos + 1,
+            });
+        }
+
+        return Ok(QueryExpr::Raw(text(chars)));
+    }
+
+    let range = value
+        .windows(2)
+        .position(|w| w.iter().all(|c| c.c == '.' && !c.quoted));
+
+    match (prefix, range) {
+        (Some(prefix @ Prefix::Date), range) | (Some(prefix @ Prefix::Lastmod), range) => {
+            let (since, until) = match range {
+                Some(i) => (&value[..i], &value[i + 2..]),
+                // A single value is the range from that value to itself.
+                None => (value, value),
+            };
+
+            range_expr(prefix, since, until, span_of(value, &span))
+        }
+        (Some(prefix), Some(i)) => Err(ParseError {
+            kind: ParseErrorKind::InvalidRange(format!(
+                "`{}:` does not support ranges",
+                prefix.as_str()
+            )),
+            span: span_of(&value[i..i + 2], &span),
+        }),
+        (Some(prefix), None) => Ok(QueryExpr::Term(prefix, text(value))),
+        (None, _) => match header {
+            Some(header) => Ok(QueryExpr::Header(header, text(value))),
+            None => Ok(QueryExpr::Text(text(value))),
+        },
+    }
+}
+
+fn range_expr(
+    prefix: Prefix,
+    since: &[WordChar],
+    until: &[WordChar],
+    span: Range<usize>,
+) -> Result<QueryExpr, ParseError> {
+    if since.is_empty() && until.is_empty() {
+        return Err(ParseError {
+            kind: ParseErrorKind::InvalidRange("both bounds are missing".to_string()),
+            span,
+        });
+    }
+
+    let bound = |chars: &[WordChar]| -> Result<Option<String>, ParseError> {
+        if chars.is_empty() {
+            return Ok(None);
+        }
+
+        let value = text(chars);
+
+        if prefix == Prefix::Lastmod && value.parse::<u64>().is_err() {
+            return Err(ParseError {
+                kind: ParseErrorKind::InvalidRange(format!("`{}` is not a revision", value)),
+                span: span_of(chars, &span),
+            });
+        }
+
+        Ok(Some(value))
+    };
+
+    Ok(QueryExpr::Range(prefix, bound(since)?, bound(until)?))
+}
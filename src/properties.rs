@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+use notmuch_sys::{
+    notmuch_message_properties_destroy, notmuch_message_properties_key,
+    notmuch_message_properties_move_to_next, notmuch_message_properties_t,
+    notmuch_message_properties_valid, notmuch_message_properties_value,
+};
+use std::{ffi::CStr, marker::PhantomData};
+
+/// Iterator over `(key, value)` pairs of message properties, borrowing the message.
+pub struct Properties<'a> {
+    ptr: *mut notmuch_message_properties_t,
+    _covariant: PhantomData<&'a ()>,
+}
+
+impl Properties<'_> {
+    /// Creates a `Properties` iterator from a given raw `ptr`
+    ///
+    /// If `ptr` is NULL, this returns None.
+    pub fn from(ptr: *mut notmuch_message_properties_t) -> Option<Self> {
+        if ptr.is_null() {
+            return None;
+        }
+
+        Some(Properties {
+            ptr,
+            _covariant: PhantomData,
+        })
+    }
+}
+
+impl Iterator for Properties<'_> {
+    type Item = (String, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if unsafe { notmuch_message_properties_valid(self.ptr) } == 0 {
+            return None;
+        }
+
+        let (key, value) = unsafe {
+            (
+                CStr::from_ptr(notmuch_message_properties_key(self.ptr)),
+                CStr::from_ptr(notmuch_message_properties_value(self.ptr)),
+            )
+        };
+        let property = (
+            key.to_string_lossy().into_owned(),
+            value.to_string_lossy().into_owned(),
+        );
+
+        unsafe { notmuch_message_properties_move_to_next(self.ptr) }
+
+        Some(property)
+    }
+}
+
+impl Drop for Properties<'_> {
+    fn drop(&mut self) {
+        unsafe { notmuch_message_properties_destroy(self.ptr) }
+    }
+}
@@ -1,21 +1,37 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
+#[cfg(notmuch_0_32)]
+use crate::config::ConfigKey;
 use crate::status::Status;
-use crate::{db::Database, message::Messages};
+use crate::{
+    db::Database,
+    message::{Message, Messages},
+    thread::{Thread, Threads},
+};
 use notmuch_sys::{
     notmuch_exclude_t, notmuch_exclude_t_NOTMUCH_EXCLUDE_ALL,
     notmuch_exclude_t_NOTMUCH_EXCLUDE_FALSE, notmuch_exclude_t_NOTMUCH_EXCLUDE_FLAG,
     notmuch_exclude_t_NOTMUCH_EXCLUDE_TRUE, notmuch_query_add_tag_exclude, notmuch_query_create,
-    notmuch_query_destroy, notmuch_query_search_messages, notmuch_query_set_omit_excluded,
-    notmuch_query_t,
+    notmuch_query_destroy, notmuch_query_search_messages, notmuch_query_search_threads,
+    notmuch_query_set_omit_excluded, notmuch_query_set_sort, notmuch_query_t, notmuch_sort_t,
+    notmuch_sort_t_NOTMUCH_SORT_MESSAGE_ID, notmuch_sort_t_NOTMUCH_SORT_NEWEST_FIRST,
+    notmuch_sort_t_NOTMUCH_SORT_OLDEST_FIRST, notmuch_sort_t_NOTMUCH_SORT_UNSORTED,
+};
+#[cfg(notmuch_0_34)]
+use notmuch_sys::{
+    notmuch_query_create_with_syntax, notmuch_query_syntax_t,
+    notmuch_query_syntax_t_NOTMUCH_QUERY_SYNTAX_SEXP,
+    notmuch_query_syntax_t_NOTMUCH_QUERY_SYNTAX_XAPIAN,
 };
-use std::{ffi::CString, marker::PhantomData};
+use std::{cell::Cell, ffi::CString, marker::PhantomData};
 
 pub struct Query<'a> {
     query: *mut notmuch_query_t,
+    offset: Cell<usize>,
+    limit: Cell<Option<usize>>,
     _covariant: PhantomData<&'a ()>,
 }
 
-impl<'a> Query<'_> {
+impl<'a> Query<'a> {
     /// Add a tag that will be excluded from the query results by default.
     ///
     /// This exclusion will be ignored if this tag appears explicitly in the query.
@@ -30,6 +46,24 @@ impl<'a> Query<'_> {
         }
     }
 
+    /// Start building a query for a `database` that honors the configured `search.exclude_tags`.
+    ///
+    /// This mirrors what `notmuch search` does by default. Before notmuch 0.32, the configuration
+    /// isn't available to libnotmuch, so only tags given to `QueryBuilder::exclude_tags` are
+    /// excluded.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let query = Query::builder(&db, "tag:inbox")
+    ///     .sort(Sort::NewestFirst)
+    ///     .limit(20)
+    ///     .build()?;
+    /// ```
+    pub fn builder(db: &'a Database, query: &str) -> QueryBuilder<'a> {
+        QueryBuilder::new(db, query)
+    }
+
     /// Create a new query for a `database`
     pub fn create(db: &'a Database, query: &str) -> Query<'a> {
         let query = CString::new(query).expect("Unable to convert query to C string");
@@ -40,6 +74,79 @@ impl<'a> Query<'_> {
         }
     }
 
+    /// Create a new query for a `database`, written in the given query `syntax`.
+    ///
+    /// `QuerySyntax::Sexp` requires notmuch 0.34 or later, built with S-expression query
+    /// support; otherwise this fails with `Status::UnsupportedOperation`. Syntax errors in the
+    /// query are reported as `Status::BadQuerySyntax`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let expr = QueryExpr::tag("inbox").and(QueryExpr::from("alice@example"));
+    /// let query = Query::create_with_syntax(&db, &expr.to_sexp(), QuerySyntax::Sexp)?;
+    /// ```
+    pub fn create_with_syntax(
+        db: &'a Database,
+        query: &str,
+        syntax: QuerySyntax,
+    ) -> Result<Query<'a>, Status> {
+        if syntax == QuerySyntax::Sexp && !QuerySyntax::sexp_supported() {
+            return Err(Status::UnsupportedOperation);
+        }
+
+        Self::create_parsed(db, query, syntax)
+    }
+
+    #[cfg(notmuch_0_34)]
+    fn create_parsed(
+        db: &'a Database,
+        query: &str,
+        syntax: QuerySyntax,
+    ) -> Result<Query<'a>, Status> {
+        let query = CString::new(query).expect("Unable to convert query to C string");
+        let mut query_ptr = std::ptr::null_mut();
+
+        let st = unsafe {
+            notmuch_query_create_with_syntax(
+                db.into(),
+                query.as_ptr(),
+                syntax.into(),
+                &mut query_ptr,
+            )
+        }
+        .into();
+
+        match st {
+            Status::Success => Query::from(query_ptr).ok_or(Status::OutOfMemory),
+            _ => Err(st),
+        }
+    }
+
+    /// Before notmuch 0.34, only the Xapian syntax exists, which `create` uses.
+    #[cfg(not(notmuch_0_34))]
+    fn create_parsed(
+        db: &'a Database,
+        query: &str,
+        _syntax: QuerySyntax,
+    ) -> Result<Query<'a>, Status> {
+        Ok(Self::create(db, query))
+    }
+
+    /// Create a query for all messages modified after `revision` of the database `uuid`.
+    ///
+    /// Returns `None` if the database UUID no longer matches `uuid`, in which case revisions are
+    /// not comparable and every message has to be considered changed.
+    pub fn modified_since(db: &'a Database, uuid: &str, revision: u64) -> Option<Query<'a>> {
+        let (_, db_uuid) = db.revision();
+
+        if db_uuid != uuid {
+            return None;
+        }
+
+        Some(Query::create(db, &lastmod_since(revision)))
+    }
+
     pub fn from(ptr: *mut notmuch_query_t) -> Option<Self> {
         // TODO(austin-ray): See if there's a Rust trait similar to From, but returns an Option.
         if ptr.is_null() {
@@ -48,26 +155,120 @@ impl<'a> Query<'_> {
 
         Some(Query {
             query: ptr,
+            offset: Cell::new(0),
+            limit: Cell::new(None),
             _covariant: PhantomData,
         })
     }
 
     /// Execute a query for messages.
-    pub fn search_messages(&self) -> Result<Messages, Status> {
+    ///
+    /// Any offset or limit set on the query is applied to the returned `Messages`.
+    pub fn search_messages(&self) -> Result<Messages<'_>, Status> {
         let mut msgs = std::ptr::null_mut();
 
         let st = unsafe { notmuch_query_search_messages(self.query, &mut msgs) }.into();
 
         match st {
-            Status::Success => Ok(Messages::from(msgs)),
+            Status::Success => Ok(Messages::from(msgs).window(self.offset.get(), self.limit.get())),
             _ => Err(st),
         }
     }
 
+    /// Execute a query for a single page of at most `limit` messages, starting at `offset`.
+    ///
+    /// Skipped messages are never loaded, and the returned `Page` reports whether further
+    /// messages exist. This ignores any offset or limit already set on the query.
+    ///
+    /// The page borrows the query, as its results are freed along with it:
+    ///
+    /// ```compile_fail
+    /// use notmuch_rs::{db::Database, message::Message, query::{Page, Query}};
+    ///
+    /// fn leak(db: &Database) -> Page<'_, Message<'_>> {
+    ///     Query::create(db, "*").search_messages_page(0, 10).unwrap()
+    /// }
+    /// ```
+    pub fn search_messages_page<'q>(
+        &'q self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Page<'q, Message<'q>>, Status> {
+        let mut msgs = std::ptr::null_mut();
+
+        let st = unsafe { notmuch_query_search_messages(self.query, &mut msgs) }.into();
+
+        if st != Status::Success {
+            return Err(st);
+        }
+
+        let msgs: Messages<'q> = Messages::from(msgs);
+        let mut iter = msgs.into_iter().skip_raw(offset);
+        let items = iter.by_ref().take(limit).collect();
+        let has_more = iter.has_next();
+
+        Ok(Page::new(items, has_more, msgs))
+    }
+
+    /// Execute a query for threads.
+    ///
+    /// Any offset or limit set on the query is applied to the returned `Threads`.
+    pub fn search_threads(&self) -> Result<Threads<'_>, Status> {
+        let mut threads = std::ptr::null_mut();
+
+        let st = unsafe { notmuch_query_search_threads(self.query, &mut threads) }.into();
+
+        match st {
+            Status::Success => {
+                Ok(Threads::from(threads).window(self.offset.get(), self.limit.get()))
+            }
+            _ => Err(st),
+        }
+    }
+
+    /// Execute a query for a single page of at most `limit` threads, starting at `offset`.
+    ///
+    /// See `search_messages_page`.
+    pub fn search_threads_page<'q>(
+        &'q self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Page<'q, Thread<'q>>, Status> {
+        let mut threads = std::ptr::null_mut();
+
+        let st = unsafe { notmuch_query_search_threads(self.query, &mut threads) }.into();
+
+        if st != Status::Success {
+            return Err(st);
+        }
+
+        let threads: Threads<'q> = Threads::from(threads);
+        let mut iter = threads.into_iter().skip_raw(offset);
+        let items = iter.by_ref().take(limit).collect();
+        let has_more = iter.has_next();
+
+        Ok(Page::new(items, has_more, threads))
+    }
+
+    /// Skip the first `offset` results when searching.
+    pub fn set_offset(&self, offset: usize) {
+        self.offset.set(offset);
+    }
+
+    /// Return at most `limit` results when searching.
+    pub fn set_limit(&self, limit: Option<usize>) {
+        self.limit.set(limit);
+    }
+
     /// Specify whether to omit excluded results or simply flag them.
     pub fn set_omit_excluded(&self, exclude: Exclude) {
         unsafe { notmuch_query_set_omit_excluded(self.query, exclude.into()) }
     }
+
+    /// Specify the sorting order of the query results.
+    pub fn set_sort(&self, sort: Sort) {
+        unsafe { notmuch_query_set_sort(self.query, sort.into()) }
+    }
 }
 
 impl Drop for Query<'_> {
@@ -78,6 +279,152 @@ impl Drop for Query<'_> {
     }
 }
 
+/// Build a `lastmod:` query term matching every message modified after `revision`.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!("lastmod:42..", notmuch_rs::query::lastmod_since(41));
+/// ```
+pub fn lastmod_since(revision: u64) -> String {
+    format!("lastmod:{}..", revision.saturating_add(1))
+}
+
+/// Search results owned by a `Page`, kept alive for as long as its items.
+trait Results {}
+
+impl Results for Messages<'_> {}
+
+impl Results for Threads<'_> {}
+
+/// A single page of search results, returned by `Query::search_messages_page` and
+/// `Query::search_threads_page`.
+pub struct Page<'a, T> {
+    items: Vec<T>,
+    has_more: bool,
+    // NOTE: Declared last so the items are dropped before the results owning them.
+    _results: Box<dyn Results + 'a>,
+}
+
+impl<'a, T> Page<'a, T> {
+    fn new<R: Results + 'a>(items: Vec<T>, has_more: bool, results: R) -> Self {
+        Page {
+            items,
+            has_more,
+            _results: Box::new(results),
+        }
+    }
+
+    /// Whether more results exist after this page.
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    /// The results on this page.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Number of results on this page.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether this page holds no results.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// Builder for a `Query`, created by `Query::builder`.
+pub struct QueryBuilder<'a> {
+    db: &'a Database,
+    query: String,
+    exclude_tags: Option<Vec<String>>,
+    exclude: Option<Exclude>,
+    sort: Option<Sort>,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    fn new(db: &'a Database, query: &str) -> Self {
+        QueryBuilder {
+            db,
+            query: query.to_string(),
+            exclude_tags: None,
+            exclude: None,
+            sort: None,
+            offset: 0,
+            limit: None,
+        }
+    }
+
+    /// Exclude `tags` instead of the ones configured in `search.exclude_tags`.
+    ///
+    /// Pass an empty slice to disable tag exclusion altogether.
+    pub fn exclude_tags(mut self, tags: &[&str]) -> Self {
+        self.exclude_tags = Some(tags.iter().map(|tag| tag.to_string()).collect());
+        self
+    }
+
+    /// Specify whether to omit excluded results or simply flag them.
+    pub fn omit_excluded(mut self, exclude: Exclude) -> Self {
+        self.exclude = Some(exclude);
+        self
+    }
+
+    /// Specify the sorting order of the query results.
+    pub fn sort(mut self, sort: Sort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Skip the first `offset` results.
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Return at most `limit` results.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Create the query and apply the tag exclusions.
+    pub fn build(self) -> Result<Query<'a>, Status> {
+        let query = Query::create(self.db, &self.query);
+
+        let tags = match self.exclude_tags {
+            Some(tags) => tags,
+            None => configured_exclude_tags(self.db),
+        };
+
+        for tag in tags.iter().filter(|tag| !tag.is_empty()) {
+            // Tags mentioned explicitly in the query are not excluded, which notmuch reports as
+            // `Ignored`.
+            match query.add_tag_exclude(tag) {
+                Ok(()) | Err(Status::Ignored) => {}
+                Err(st) => return Err(st),
+            }
+        }
+
+        if let Some(exclude) = self.exclude {
+            query.set_omit_excluded(exclude);
+        }
+
+        if let Some(sort) = self.sort {
+            query.set_sort(sort);
+        }
+
+        query.set_offset(self.offset);
+        query.set_limit(self.limit);
+
+        Ok(query)
+    }
+}
+
 pub enum Exclude {
     True,
     False,
@@ -95,3 +442,69 @@ impl From<Exclude> for notmuch_exclude_t {
         }
     }
 }
+
+/// Returns the tags configured in `search.exclude_tags`.
+#[cfg(notmuch_0_32)]
+fn configured_exclude_tags(db: &Database) -> Vec<String> {
+    db.config_values(ConfigKey::ExcludeTags)
+        .map(|values| values.collect())
+        .unwrap_or_default()
+}
+
+/// Before notmuch 0.32, `search.exclude_tags` is only in the configuration file, which libnotmuch
+/// doesn't read.
+#[cfg(not(notmuch_0_32))]
+fn configured_exclude_tags(_db: &Database) -> Vec<String> {
+    Vec::new()
+}
+
+/// Language a query string is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuerySyntax {
+    /// The default query syntax, parsed by the Xapian query parser.
+    Xapian,
+    /// S-expression query syntax, see `notmuch-sexp-queries(7)`.
+    Sexp,
+}
+
+impl QuerySyntax {
+    /// Whether the linked libnotmuch was built with S-expression query support.
+    ///
+    /// Always `false` before notmuch 0.34.
+    pub fn sexp_supported() -> bool {
+        cfg!(notmuch_0_34) && crate::version::built_with("sexp_queries")
+    }
+}
+
+#[cfg(notmuch_0_34)]
+impl From<QuerySyntax> for notmuch_query_syntax_t {
+    fn from(syntax: QuerySyntax) -> Self {
+        match syntax {
+            QuerySyntax::Xapian => notmuch_query_syntax_t_NOTMUCH_QUERY_SYNTAX_XAPIAN,
+            QuerySyntax::Sexp => notmuch_query_syntax_t_NOTMUCH_QUERY_SYNTAX_SEXP,
+        }
+    }
+}
+
+/// Sorting order of query results.
+pub enum Sort {
+    /// Oldest messages first.
+    OldestFirst,
+    /// Newest messages first.
+    NewestFirst,
+    /// Sort by message ID.
+    MessageId,
+    /// Do not sort.
+    Unsorted,
+}
+
+impl From<Sort> for notmuch_sort_t {
+    fn from(sort: Sort) -> Self {
+        match sort {
+            Sort::OldestFirst => notmuch_sort_t_NOTMUCH_SORT_OLDEST_FIRST,
+            Sort::NewestFirst => notmuch_sort_t_NOTMUCH_SORT_NEWEST_FIRST,
+            Sort::MessageId => notmuch_sort_t_NOTMUCH_SORT_MESSAGE_ID,
+            Sort::Unsorted => notmuch_sort_t_NOTMUCH_SORT_UNSORTED,
+        }
+    }
+}
@@ -0,0 +1,347 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Composing replies to messages, like `notmuch reply`.
+//!
+//! A reply is addressed like `notmuch reply --format=headers-only` does and quotes the text parts
+//! of the message like the default format. The user's own addresses, `user.primary_email` and
+//! `user.other_email`, are never added as recipients. Instead, the one the message was sent to is
+//! used as the sender of the reply.
+//!
+//! This module requires the `mime` feature and notmuch 0.32 or later.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let draft = reply::create(&db, &msg, ReplyMode::All)?;
+//!
+//! fs::write("draft.eml", draft.to_string())?;
+//! ```
+use crate::{
+    config::ConfigKey,
+    db::Database,
+    message::Message,
+    mime::{strip_line_break, MimeError},
+};
+use mailparse::{addrparse_header, DispositionType, MailAddr, MailHeaderMap, ParsedMail};
+use std::{fmt::Display, fs};
+
+/// Prefix of a subject marking it as a reply, compared case-insensitively.
+const REPLY_PREFIX: &str = "Re:";
+
+/// Headers the message may have been delivered to the user through, in order of preference.
+const DELIVERY_HEADERS: &[&str] = &[
+    "Reply-To",
+    "From",
+    "To",
+    "Cc",
+    "Bcc",
+    "Envelope-To",
+    "X-Original-To",
+    "Delivered-To",
+];
+
+/// Parts of PGP/MIME and S/MIME messages that are left out of the quoted body.
+const CRYPTO_CONTENT_TYPES: &[&str] = &[
+    "application/pgp-encrypted",
+    "application/pgp-signature",
+    "application/pkcs7-mime",
+];
+
+/// Whom to address a reply to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyMode {
+    /// Only the sender, or the address in `Reply-To`.
+    Sender,
+    /// The sender and all recipients of the message.
+    All,
+}
+
+/// A mailbox, e.g. `Jane Doe <jdoe@example>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    /// Display name, if any.
+    pub name: Option<String>,
+    /// Address, e.g. `jdoe@example`.
+    pub address: String,
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) if name.contains(|c| "()<>[]:;@\\,.\"".contains(c)) => write!(
+                f,
+                "\"{}\" <{}>",
+                name.replace('\\', "\\\\").replace('"', "\\\""),
+                self.address
+            ),
+            Some(name) => write!(f, "{} <{}>", name, self.address),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+/// Draft of a reply, see `create`.
+///
+/// Its `Display` implementation formats it like `notmuch reply`: the headers, an empty line and
+/// the quoted body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    /// The user's address the message was sent to, or else `user.primary_email`.
+    pub from: Address,
+    /// Subject of the message, prefixed with `Re:` unless it already is.
+    pub subject: String,
+    /// The sender, and with `ReplyMode::All` the `To` recipients of the message.
+    pub to: Vec<Address>,
+    /// With `ReplyMode::All`, the `Cc` recipients of the message.
+    pub cc: Vec<Address>,
+    /// With `ReplyMode::All`, the `Bcc` recipients of the message, if it has any.
+    pub bcc: Vec<Address>,
+    /// Message ID of the message, in angle brackets.
+    pub in_reply_to: String,
+    /// The references of the message followed by its message ID.
+    pub references: String,
+    /// Attribution line followed by the quoted text of the message.
+    pub body: String,
+}
+
+impl Reply {
+    /// Returns the headers of the reply as `(name, value)` pairs, leaving out empty address
+    /// lists.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            ("From", self.from.to_string()),
+            ("Subject", self.subject.clone()),
+        ];
+
+        for &(name, addresses) in &[("To", &self.to), ("Cc", &self.cc), ("Bcc", &self.bcc)] {
+            if !addresses.is_empty() {
+                let addresses: Vec<_> = addresses.iter().map(Address::to_string).collect();
+                headers.push((name, addresses.join(", ")));
+            }
+        }
+
+        headers.push(("In-Reply-To", self.in_reply_to.clone()));
+        headers.push(("References", self.references.clone()));
+
+        headers
+    }
+}
+
+impl Display for Reply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in self.headers() {
+            writeln!(f, "{}: {}", name, value)?;
+        }
+
+        write!(f, "\n{}", self.body)
+    }
+}
+
+/// Compose a reply to `msg`, reading the user's addresses and name from the configuration of
+/// `db`.
+pub fn create(db: &Database, msg: &Message, mode: ReplyMode) -> Result<Reply, MimeError> {
+    let raw = fs::read(msg.filename().ok_or(MimeError::NoFilename)?)?;
+    let mail = mailparse::parse_mail(&raw)?;
+
+    let own: Vec<_> = db
+        .config_key(ConfigKey::PrimaryEmail)
+        .into_iter()
+        .chain(
+            db.config_values(ConfigKey::OtherEmail)
+                .into_iter()
+                .flatten(),
+        )
+        .filter(|address| !address.is_empty())
+        .collect();
+    let user = Address {
+        name: db
+            .config_key(ConfigKey::UserName)
+            .filter(|name| !name.is_empty()),
+        address: own.first().cloned().unwrap_or_default(),
+    };
+
+    compose(&mail, &msg.message_id(), &own, user, mode)
+}
+
+/// Compose a reply to `mail` with message ID `id`, on behalf of `user` with the addresses `own`.
+fn compose(
+    mail: &ParsedMail,
+    id: &str,
+    own: &[String],
+    user: Address,
+    mode: ReplyMode,
+) -> Result<Reply, MimeError> {
+    let own_address = |address: &Address| {
+        own.iter()
+            .find(|own| own.eq_ignore_ascii_case(&address.address))
+            .cloned()
+    };
+
+    let from = DELIVERY_HEADERS
+        .iter()
+        .flat_map(|header| addresses(mail, header))
+        .find_map(|address| own_address(&address))
+        .map(|address| Address {
+            name: user.name.clone(),
+            address,
+        })
+        .unwrap_or(user);
+
+    // Mailing lists may set `Reply-To` to the list, which is then a recipient already.
+    let sender = if reply_to_is_redundant(mail) || addresses(mail, "Reply-To").is_empty() {
+        "From"
+    } else {
+        "Reply-To"
+    };
+
+    let mut recipients = [Vec::new(), Vec::new(), Vec::new()];
+    let mut seen = Vec::new();
+    for &(header, field) in &[(sender, 0), ("To", 0), ("Cc", 1), ("Bcc", 2)] {
+        for address in addresses(mail, header) {
+            let key = address.address.to_lowercase();
+
+            if own_address(&address).is_some() || seen.contains(&key) {
+                continue;
+            }
+
+            seen.push(key);
+            recipients[field].push(address);
+        }
+
+        // If the sender is the user, this falls through to the recipients of the message.
+        if mode == ReplyMode::Sender && !seen.is_empty() {
+            break;
+        }
+    }
+    let [to, cc, bcc] = recipients;
+
+    let subject = mail.headers.get_first_value("Subject").unwrap_or_default();
+    let subject = match subject.get(..REPLY_PREFIX.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(REPLY_PREFIX) => subject,
+        _ => format!("{} {}", REPLY_PREFIX, subject),
+    };
+
+    let in_reply_to = format!("<{}>", id);
+    let references = ["References", "In-Reply-To"]
+        .iter()
+        .filter_map(|header| mail.headers.get_first_value(header))
+        .map(|references| references.split_whitespace().collect::<Vec<_>>().join(" "))
+        .find(|references| !references.is_empty())
+        .map(|references| format!("{} {}", references, in_reply_to))
+        .unwrap_or_else(|| in_reply_to.clone());
+
+    let mut body = format!(
+        "On {}, {} wrote:\n",
+        mail.headers.get_first_value("Date").unwrap_or_default(),
+        mail.headers.get_first_value("From").unwrap_or_default()
+    );
+    quote(mail, false, &mut body)?;
+
+    Ok(Reply {
+        from,
+        subject,
+        to,
+        cc,
+        bcc,
+        in_reply_to,
+        references,
+        body,
+    })
+}
+
+/// Returns the mailboxes in the first `header` of `mail`, ignoring malformed ones.
+fn addresses(mail: &ParsedMail, header: &str) -> Vec<Address> {
+    let list = match mail.headers.get_first_header(header).map(addrparse_header) {
+        Some(Ok(list)) => list.into_inner(),
+        _ => return Vec::new(),
+    };
+
+    list.into_iter()
+        .flat_map(|address| match address {
+            MailAddr::Single(single) => vec![single],
+            MailAddr::Group(group) => group.addrs,
+        })
+        .map(|single| Address {
+            name: single.display_name,
+            address: single.addr,
+        })
+        .collect()
+}
+
+/// Whether `Reply-To` holds a single address that is also in `To` or `Cc`, as set by mailing
+/// lists.
+fn reply_to_is_redundant(mail: &ParsedMail) -> bool {
+    let reply_to = match addresses(mail, "Reply-To").as_slice() {
+        [reply_to] => reply_to.address.to_lowercase(),
+        _ => return false,
+    };
+
+    ["To", "Cc"].iter().any(|header| {
+        matches!(
+            mail.headers.get_first_value(header),
+            Some(value) if value.to_lowercase().contains(&reply_to)
+        )
+    })
+}
+
+/// Append the quoted text of `mail` to `out`, describing the parts that aren't text.
+fn quote(mail: &ParsedMail, in_multipart: bool, out: &mut String) -> Result<(), MimeError> {
+    let content_type = mail.ctype.mimetype.as_str();
+
+    if content_type.starts_with("multipart/") {
+        for sub in &mail.subparts {
+            quote(sub, true, out)?;
+        }
+    } else if content_type == "message/rfc822" {
+        let raw = mail.get_body_raw()?;
+        let embedded = mailparse::parse_mail(&raw)?;
+
+        for header in &["From", "To", "Cc", "Subject", "Date"] {
+            if let Some(value) = embedded.headers.get_first_value(header) {
+                quote_line(&format!("{}: {}", header, value), out);
+            }
+        }
+        quote_line("", out);
+
+        quote(&embedded, false, out)?;
+    } else if CRYPTO_CONTENT_TYPES.contains(&content_type) {
+        // Signatures and encryption metadata aren't worth quoting.
+    } else if content_type.starts_with("text/") && content_type != "text/html" {
+        let mut text = mail.get_body()?;
+        if in_multipart {
+            text.truncate(strip_line_break(text.as_bytes()).len());
+        }
+
+        for line in text.lines() {
+            quote_line(line, out);
+        }
+    } else {
+        let disposition = mail.get_content_disposition();
+
+        let line = if disposition.disposition == DispositionType::Attachment {
+            let filename = disposition
+                .params
+                .get("filename")
+                .or_else(|| mail.ctype.params.get("name"))
+                .map_or("", String::as_str);
+
+            format!("Attachment: {} ({})", filename, content_type)
+        } else {
+            format!("Non-text part: {}", content_type)
+        };
+
+        quote_line(&line, out);
+    }
+
+    Ok(())
+}
+
+/// Append `line` to `out` as a quoted line.
+fn quote_line(line: &str, out: &mut String) {
+    if line.is_empty() {
+        out.push_str(">\n");
+    } else {
+        out.push_str("> ");
+        out.push_str(line);
+        out.push('\n');
+    }
+}
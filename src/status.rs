@@ -2,7 +2,7 @@ use std::{error::Error, ffi::CStr, fmt::Display};
 
 use notmuch_sys::{notmuch_status_t, notmuch_status_to_string};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Status {
     /// No error occurred.
     Success,
@@ -42,6 +42,9 @@ pub enum Status {
     UnsupportedOperation,
 
     /// The operation requires a database upgrade.
+    ///
+    /// Open the database in read-write mode and call `Database::upgrade`, or run `notmuch new`,
+    /// which upgrades the database automatically.
     UpgradeRequired,
 
     /// There is a problem with the proposed path, e.g. a relative path passed to a function
@@ -77,6 +80,24 @@ pub enum Status {
     /// Database exists, so not (re)-created
     DatabaseExists,
 
+    /// Syntax error in query
+    ///
+    /// Requires notmuch 0.32 or later.
+    #[cfg(notmuch_0_32)]
+    BadQuerySyntax,
+
+    /// No mail root could be deduced from parameters and environment
+    ///
+    /// Requires notmuch 0.33 or later.
+    #[cfg(notmuch_0_33)]
+    NoMailRoot,
+
+    /// Database is not fully opened, or has been closed
+    ///
+    /// Requires notmuch 0.34 or later.
+    #[cfg(notmuch_0_34)]
+    ClosedDatabase,
+
     /// Not an actual status value. Just a way to find out how many valid status values there are.
     LastStatus,
 }
@@ -134,6 +155,12 @@ impl From<notmuch_status_t> for Status {
             notmuch_sys::_notmuch_status_NOTMUCH_STATUS_NO_CONFIG => Self::NoConfig,
             notmuch_sys::_notmuch_status_NOTMUCH_STATUS_NO_DATABASE => Self::NoDatabase,
             notmuch_sys::_notmuch_status_NOTMUCH_STATUS_DATABASE_EXISTS => Self::DatabaseExists,
+            #[cfg(notmuch_0_32)]
+            notmuch_sys::_notmuch_status_NOTMUCH_STATUS_BAD_QUERY_SYNTAX => Self::BadQuerySyntax,
+            #[cfg(notmuch_0_33)]
+            notmuch_sys::_notmuch_status_NOTMUCH_STATUS_NO_MAIL_ROOT => Self::NoMailRoot,
+            #[cfg(notmuch_0_34)]
+            notmuch_sys::_notmuch_status_NOTMUCH_STATUS_CLOSED_DATABASE => Self::ClosedDatabase,
             notmuch_sys::_notmuch_status_NOTMUCH_STATUS_LAST_STATUS => Self::LastStatus,
             _ => Self::LastStatus,
         }
@@ -181,6 +208,12 @@ impl From<Status> for notmuch_status_t {
             Status::NoConfig => notmuch_sys::_notmuch_status_NOTMUCH_STATUS_NO_CONFIG,
             Status::NoDatabase => notmuch_sys::_notmuch_status_NOTMUCH_STATUS_NO_DATABASE,
             Status::DatabaseExists => notmuch_sys::_notmuch_status_NOTMUCH_STATUS_DATABASE_EXISTS,
+            #[cfg(notmuch_0_32)]
+            Status::BadQuerySyntax => notmuch_sys::_notmuch_status_NOTMUCH_STATUS_BAD_QUERY_SYNTAX,
+            #[cfg(notmuch_0_33)]
+            Status::NoMailRoot => notmuch_sys::_notmuch_status_NOTMUCH_STATUS_NO_MAIL_ROOT,
+            #[cfg(notmuch_0_34)]
+            Status::ClosedDatabase => notmuch_sys::_notmuch_status_NOTMUCH_STATUS_CLOSED_DATABASE,
             Status::LastStatus => notmuch_sys::_notmuch_status_NOTMUCH_STATUS_LAST_STATUS,
         }
     }
@@ -188,6 +221,6 @@ impl From<Status> for notmuch_status_t {
 
 impl From<&Status> for notmuch_status_t {
     fn from(st: &Status) -> Self {
-        st.into()
+        (*st).into()
     }
 }
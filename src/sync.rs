@@ -0,0 +1,419 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Incremental tag synchronization between two notmuch databases.
+//!
+//! Each run only looks at messages modified since the revisions recorded in a `SyncState`, and
+//! merges their tags against the tags both databases agreed on after the previous run. A tag
+//! changed on one side only is propagated to the other side. If there is no previous agreement,
+//! e.g. on the first run, a `ConflictPolicy` decides.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut state = SyncState::load(&state_path)?;
+//! let summary = sync::sync(&laptop, &server, &mut state, &Strategy::Union)?;
+//! state.save(&state_path)?;
+//! ```
+#[cfg(notmuch_0_32)]
+use crate::config::ConfigKey;
+use crate::{
+    db::Database,
+    dump::{hex_decode, hex_encode},
+    query::Query,
+    status::Status,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+type TagSet = BTreeSet<String>;
+
+/// First line of a state file.
+const STATE_HEADER: &str = "# notmuch-rs sync state v1";
+
+/// Revision of a database at the time of the last synchronization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub uuid: String,
+    pub revision: u64,
+}
+
+/// Persistent synchronization state.
+///
+/// The state is stored as text, with the agreed tags of each message in the `notmuch dump`
+/// batch-tag format.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SyncState {
+    local: Option<Checkpoint>,
+    remote: Option<Checkpoint>,
+    tags: BTreeMap<String, TagSet>,
+}
+
+impl SyncState {
+    /// Load the state stored at `path`.
+    ///
+    /// A missing file yields an empty state, which makes the next synchronization a full one.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(SyncState::default()),
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = content.lines();
+
+        if lines.next() != Some(STATE_HEADER) {
+            return Err(invalid_data("missing sync state header"));
+        }
+
+        let mut state = SyncState::default();
+
+        for line in lines {
+            let mut words = line.split(' ');
+
+            match words.next() {
+                Some("local") => state.local = Some(parse_checkpoint(words)?),
+                Some("remote") => state.remote = Some(parse_checkpoint(words)?),
+                _ => {
+                    let (tags, id) = parse_tag_line(line)?;
+                    state.tags.insert(id, tags);
+                }
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Store the state at `path`.
+    ///
+    /// The file is replaced atomically, so an interrupted save never leaves a truncated state.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let mut file = io::BufWriter::new(fs::File::create(&tmp_path)?);
+        writeln!(file, "{}", STATE_HEADER)?;
+
+        for (side, checkpoint) in [("local", &self.local), ("remote", &self.remote)] {
+            if let Some(checkpoint) = checkpoint {
+                writeln!(
+                    file,
+                    "{} {} {}",
+                    side,
+                    hex_encode(&checkpoint.uuid),
+                    checkpoint.revision
+                )?;
+            }
+        }
+
+        for (id, tags) in &self.tags {
+            for tag in tags {
+                write!(file, "+{} ", hex_encode(tag))?;
+            }
+            writeln!(file, "-- id:{}", hex_encode(id))?;
+        }
+
+        file.into_inner()?.sync_all()?;
+        fs::rename(&tmp_path, path)
+    }
+
+    /// Checkpoint of the local database after the last synchronization.
+    pub fn local(&self) -> Option<&Checkpoint> {
+        self.local.as_ref()
+    }
+
+    /// Checkpoint of the remote database after the last synchronization.
+    pub fn remote(&self) -> Option<&Checkpoint> {
+        self.remote.as_ref()
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn parse_checkpoint<'a>(mut words: impl Iterator<Item = &'a str>) -> io::Result<Checkpoint> {
+    let uuid = words
+        .next()
+        .and_then(hex_decode)
+        .ok_or_else(|| invalid_data("invalid checkpoint uuid"))?;
+    let revision = words
+        .next()
+        .and_then(|rev| rev.parse().ok())
+        .ok_or_else(|| invalid_data("invalid checkpoint revision"))?;
+
+    Ok(Checkpoint { uuid, revision })
+}
+
+fn parse_tag_line(line: &str) -> io::Result<(TagSet, String)> {
+    let mut tags = TagSet::new();
+    let mut words = line.split(' ');
+
+    for word in words.by_ref() {
+        if word == "--" {
+            break;
+        }
+
+        let tag = word
+            .strip_prefix('+')
+            .and_then(hex_decode)
+            .ok_or_else(|| invalid_data("invalid tag"))?;
+        tags.insert(tag);
+    }
+
+    let id = words
+        .next()
+        .and_then(|word| word.strip_prefix("id:"))
+        .and_then(hex_decode)
+        .ok_or_else(|| invalid_data("invalid message id"))?;
+
+    Ok((tags, id))
+}
+
+/// Decides the tags of a message that differ between both databases without a previously agreed
+/// upon state to tell which side changed them.
+pub trait ConflictPolicy {
+    /// Whether `tag`, currently only set in the database named by `in_local`, should be set on
+    /// the message `message_id` in both databases.
+    fn keep(&self, message_id: &str, tag: &str, in_local: bool) -> bool;
+}
+
+impl<F> ConflictPolicy for F
+where
+    F: Fn(&str, &str, bool) -> bool,
+{
+    fn keep(&self, message_id: &str, tag: &str, in_local: bool) -> bool {
+        self(message_id, tag, in_local)
+    }
+}
+
+/// Built-in conflict policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Keep tags set on either side.
+    Union,
+    /// Keep only tags set on both sides.
+    Intersection,
+    /// Take the local tags.
+    PreferLocal,
+    /// Take the remote tags.
+    PreferRemote,
+}
+
+impl ConflictPolicy for Strategy {
+    fn keep(&self, _message_id: &str, _tag: &str, in_local: bool) -> bool {
+        match self {
+            Strategy::Union => true,
+            Strategy::Intersection => false,
+            Strategy::PreferLocal => in_local,
+            Strategy::PreferRemote => !in_local,
+        }
+    }
+}
+
+/// Outcome of a synchronization run.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncSummary {
+    /// Number of messages whose tags were changed in the local database.
+    pub local_updated: usize,
+    /// Number of messages whose tags were changed in the remote database.
+    pub remote_updated: usize,
+    /// Number of tags decided by the `ConflictPolicy`.
+    pub conflicts: usize,
+}
+
+/// Synchronize the tags of the messages present in both `local` and `remote`.
+///
+/// Both databases must be opened in read-write mode. Changes to each database are applied within
+/// a single atomic section. `state` is updated in memory only; persist it with `SyncState::save`
+/// once this returns successfully.
+pub fn sync(
+    local: &Database,
+    remote: &Database,
+    state: &mut SyncState,
+    policy: &impl ConflictPolicy,
+) -> Result<SyncSummary, Status> {
+    // Revisions are taken before looking for changes, so changes made while synchronizing are
+    // picked up again by the next run instead of being lost.
+    let local_checkpoint = checkpoint(local);
+    let remote_checkpoint = checkpoint(remote);
+
+    let local_changes = changed_tags(local, state.local.as_ref())?;
+    let remote_changes = changed_tags(remote, state.remote.as_ref())?;
+
+    let ids: BTreeSet<&String> = local_changes.keys().chain(remote_changes.keys()).collect();
+    let mut summary = SyncSummary::default();
+    let mut local_updates = Vec::new();
+    let mut remote_updates = Vec::new();
+
+    for id in ids {
+        let local_tags = match local_changes.get(id) {
+            Some(tags) => Some(tags.clone()),
+            None => current_tags(local, id)?,
+        };
+        let remote_tags = match remote_changes.get(id) {
+            Some(tags) => Some(tags.clone()),
+            None => current_tags(remote, id)?,
+        };
+
+        // Messages missing on one side are picked up once they are indexed there.
+        let (local_tags, remote_tags) = match (local_tags, remote_tags) {
+            (Some(l), Some(r)) => (l, r),
+            _ => continue,
+        };
+
+        let merged = merge(
+            id,
+            state.tags.get(id),
+            &local_tags,
+            &remote_tags,
+            policy,
+            &mut summary.conflicts,
+        );
+
+        if merged != local_tags {
+            local_updates.push((id.clone(), merged.clone()));
+        }
+        if merged != remote_tags {
+            remote_updates.push((id.clone(), merged.clone()));
+        }
+
+        state.tags.insert(id.clone(), merged);
+    }
+
+    summary.local_updated = apply(local, &local_updates)?;
+    summary.remote_updated = apply(remote, &remote_updates)?;
+
+    state.local = Some(local_checkpoint);
+    state.remote = Some(remote_checkpoint);
+
+    Ok(summary)
+}
+
+fn checkpoint(db: &Database) -> Checkpoint {
+    let (revision, uuid) = db.revision();
+    Checkpoint { uuid, revision }
+}
+
+/// Tags of every message modified since `checkpoint`, or of all messages if there is none.
+fn changed_tags(
+    db: &Database,
+    checkpoint: Option<&Checkpoint>,
+) -> Result<BTreeMap<String, TagSet>, Status> {
+    let query = checkpoint
+        .and_then(|cp| Query::modified_since(db, &cp.uuid, cp.revision))
+        .unwrap_or_else(|| Query::create(db, "*"));
+
+    let msgs = query.search_messages()?;
+    let changes = msgs
+        .into_iter()
+        .map(|msg| {
+            let tags = msg
+                .get_tags()
+                .map(|tags| tags.collect())
+                .unwrap_or_default();
+            (msg.message_id(), tags)
+        })
+        .collect();
+
+    Ok(changes)
+}
+
+fn current_tags(db: &Database, id: &str) -> Result<Option<TagSet>, Status> {
+    let tags = db.find_message(id)?.map(|msg| {
+        msg.get_tags()
+            .map(|tags| tags.collect())
+            .unwrap_or_default()
+    });
+
+    Ok(tags)
+}
+
+/// Three-way merge of the tags of message `id`.
+fn merge(
+    id: &str,
+    base: Option<&TagSet>,
+    local: &TagSet,
+    remote: &TagSet,
+    policy: &impl ConflictPolicy,
+    conflicts: &mut usize,
+) -> TagSet {
+    local
+        .union(remote)
+        .filter(|tag| {
+            let in_local = local.contains(*tag);
+
+            if in_local == remote.contains(*tag) {
+                return true;
+            }
+
+            match base {
+                // Only one side changed the tag since the last agreement, so the side differing
+                // from it holds the new value, which is set exactly if it wasn't before.
+                Some(base) => !base.contains(*tag),
+                None => {
+                    *conflicts += 1;
+                    policy.keep(id, tag, in_local)
+                }
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+/// Whether `maildir.synchronize_flags` is enabled.
+#[cfg(notmuch_0_32)]
+fn sync_maildir_flags(db: &Database) -> bool {
+    db.config_bool(ConfigKey::SyncMaildirFlags).unwrap_or(false)
+}
+
+/// Before notmuch 0.32, `maildir.synchronize_flags` is only in the configuration file, which
+/// libnotmuch doesn't read.
+#[cfg(not(notmuch_0_32))]
+fn sync_maildir_flags(_db: &Database) -> bool {
+    false
+}
+
+/// Set the tags of each message, returning how many messages were updated.
+fn apply(db: &Database, updates: &[(String, TagSet)]) -> Result<usize, Status> {
+    if updates.is_empty() {
+        return Ok(0);
+    }
+
+    let sync_flags = sync_maildir_flags(db);
+
+    db.atomic(|| {
+        let mut updated = 0;
+
+        for (id, tags) in updates {
+            let msg = match db.find_message(id)? {
+                Some(msg) => msg,
+                None => continue,
+            };
+            let current: TagSet = msg
+                .get_tags()
+                .map(|tags| tags.collect())
+                .unwrap_or_default();
+
+            msg.frozen(|| {
+                for tag in current.difference(tags) {
+                    msg.remove_tag(tag)?;
+                }
+                for tag in tags.difference(&current) {
+                    msg.add_tag(tag)?;
+                }
+                Ok(())
+            })?;
+
+            if sync_flags {
+                msg.tags_to_maildir_flags()?;
+            }
+
+            updated += 1;
+        }
+
+        Ok(updated)
+    })
+}
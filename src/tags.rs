@@ -1,13 +1,15 @@
 use notmuch_sys::{
     notmuch_tags_get, notmuch_tags_move_to_next, notmuch_tags_t, notmuch_tags_valid,
 };
-use std::ffi::CStr;
+use std::{ffi::CStr, marker::PhantomData};
 
-pub struct Tags {
+/// Iterator over the tags of a message or thread, borrowing it.
+pub struct Tags<'a> {
     tags: *mut notmuch_tags_t,
+    _covariant: PhantomData<&'a ()>,
 }
 
-impl Tags {
+impl Tags<'_> {
     /// Creates a `Tags` iterator from a given raw `ptr`
     ///
     /// If `ptr` is NULL, this returns None.
@@ -16,11 +18,14 @@ impl Tags {
             return None;
         }
 
-        Some(Tags { tags: ptr })
+        Some(Tags {
+            tags: ptr,
+            _covariant: PhantomData,
+        })
     }
 }
 
-impl Iterator for Tags {
+impl Iterator for Tags<'_> {
     type Item = String;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+use crate::{message::Messages, tags::Tags};
+use notmuch_sys::{
+    notmuch_thread_get_authors, notmuch_thread_get_matched_messages, notmuch_thread_get_messages,
+    notmuch_thread_get_newest_date, notmuch_thread_get_oldest_date, notmuch_thread_get_subject,
+    notmuch_thread_get_tags, notmuch_thread_get_thread_id, notmuch_thread_get_toplevel_messages,
+    notmuch_thread_get_total_messages, notmuch_thread_t, notmuch_threads_destroy,
+    notmuch_threads_get, notmuch_threads_move_to_next, notmuch_threads_t, notmuch_threads_valid,
+};
+use std::{ffi::CStr, marker::PhantomData};
+
+pub struct Thread<'a> {
+    ptr: *mut notmuch_thread_t,
+    _covariant: PhantomData<&'a ()>,
+}
+
+impl<'a> Thread<'a> {
+    /// Get the authors of the thread as a comma-separated string.
+    ///
+    /// Authors of matched messages come first, separated from the others by a `|`.
+    pub fn authors(&self) -> Option<String> {
+        let raw_str = unsafe { notmuch_thread_get_authors(self.ptr) };
+
+        if raw_str.is_null() {
+            return None;
+        }
+
+        Some(
+            unsafe { CStr::from_ptr(raw_str) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    /// Returns a `Tags` iterator for the union of all tags of the messages in this thread.
+    pub fn get_tags(&self) -> Option<Tags<'_>> {
+        let tags_ptr = unsafe { notmuch_thread_get_tags(self.ptr) };
+        Tags::from(tags_ptr)
+    }
+
+    /// Number of messages in the thread that matched the query.
+    pub fn matched_messages(&self) -> usize {
+        unsafe { notmuch_thread_get_matched_messages(self.ptr) as usize }
+    }
+
+    /// All messages of the thread, in oldest-first order.
+    pub fn messages(&self) -> Messages<'a> {
+        Messages::from(unsafe { notmuch_thread_get_messages(self.ptr) })
+    }
+
+    /// Date of the newest message in the thread, in seconds since the epoch.
+    pub fn newest_date(&self) -> i64 {
+        unsafe { notmuch_thread_get_newest_date(self.ptr) as i64 }
+    }
+
+    /// Date of the oldest message in the thread, in seconds since the epoch.
+    pub fn oldest_date(&self) -> i64 {
+        unsafe { notmuch_thread_get_oldest_date(self.ptr) as i64 }
+    }
+
+    /// Get the subject of the thread.
+    pub fn subject(&self) -> String {
+        let raw_str = unsafe { notmuch_thread_get_subject(self.ptr) };
+
+        unsafe { CStr::from_ptr(raw_str) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Get thread id from notmuch.
+    pub fn thread_id(&self) -> String {
+        let raw_str = unsafe { notmuch_thread_get_thread_id(self.ptr) };
+
+        unsafe { CStr::from_ptr(raw_str) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Messages of the thread that are not replies to other messages in the thread.
+    pub fn toplevel_messages(&self) -> Messages<'a> {
+        Messages::from(unsafe { notmuch_thread_get_toplevel_messages(self.ptr) })
+    }
+
+    /// Total number of messages in the thread.
+    pub fn total_messages(&self) -> usize {
+        unsafe { notmuch_thread_get_total_messages(self.ptr) as usize }
+    }
+}
+
+impl From<*mut notmuch_thread_t> for Thread<'_> {
+    fn from(ptr: *mut notmuch_thread_t) -> Self {
+        Thread {
+            ptr,
+            _covariant: PhantomData,
+        }
+    }
+}
+
+/// Container for all threads corresponding to a `Query`.
+///
+/// Like `Messages`, this must be converted into an `Iterator` before use.
+pub struct Threads<'a> {
+    ptr: *mut notmuch_threads_t,
+    offset: usize,
+    limit: Option<usize>,
+    _covariant: PhantomData<&'a ()>,
+}
+
+impl Threads<'_> {
+    /// Restrict iteration to `limit` threads after skipping the first `offset`.
+    pub(crate) fn window(mut self, offset: usize, limit: Option<usize>) -> Self {
+        self.offset = offset;
+        self.limit = limit;
+        self
+    }
+}
+
+impl Drop for Threads<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            notmuch_threads_destroy(self.ptr);
+        }
+    }
+}
+
+impl From<*mut notmuch_threads_t> for Threads<'_> {
+    fn from(ptr: *mut notmuch_threads_t) -> Self {
+        Threads {
+            ptr,
+            offset: 0,
+            limit: None,
+            _covariant: PhantomData,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &Threads<'a> {
+    type Item = Thread<'a>;
+    type IntoIter = ThreadsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ThreadsIter::from_ptr(self.ptr, self.offset, self.limit)
+    }
+}
+
+// NOTE: This intentionally does not implement `Drop`. The pointer is owned by a `Threads` struct.
+pub struct ThreadsIter<'a> {
+    ptr: *mut notmuch_threads_t,
+    skip: usize,
+    remaining: Option<usize>,
+    _covariant: PhantomData<&'a ()>,
+}
+
+impl ThreadsIter<'_> {
+    fn from_ptr(ptr: *mut notmuch_threads_t, skip: usize, remaining: Option<usize>) -> Self {
+        ThreadsIter {
+            ptr,
+            skip,
+            remaining,
+            _covariant: PhantomData,
+        }
+    }
+
+    /// Skip the next `n` threads without constructing a `Thread` for them.
+    pub fn skip_raw(mut self, n: usize) -> Self {
+        self.skip += n;
+        self
+    }
+
+    /// Whether any threads are left to iterate over, ignoring the limit.
+    pub(crate) fn has_next(&mut self) -> bool {
+        self.skip_pending();
+        unsafe { notmuch_threads_valid(self.ptr) != 0 }
+    }
+
+    fn skip_pending(&mut self) {
+        while self.skip > 0 && unsafe { notmuch_threads_valid(self.ptr) != 0 } {
+            unsafe { notmuch_threads_move_to_next(self.ptr) }
+            self.skip -= 1;
+        }
+    }
+}
+
+impl<'a> Iterator for ThreadsIter<'a> {
+    type Item = Thread<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.skip_pending();
+
+        if self.remaining == Some(0) {
+            return None;
+        }
+
+        let is_valid = unsafe { notmuch_threads_valid(self.ptr) != 0 };
+
+        if !is_valid {
+            return None;
+        }
+
+        let cur_ptr = unsafe { notmuch_threads_get(self.ptr) };
+        let cur = Thread::from(cur_ptr);
+
+        // Advance the pointer
+        unsafe { notmuch_threads_move_to_next(self.ptr) }
+
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= 1;
+        }
+
+        Some(cur)
+    }
+}
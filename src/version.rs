@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! libnotmuch version and capabilities.
+//!
+//! libnotmuch has no function returning its version, so the version compiled against comes from
+//! the `LIBNOTMUCH_*_VERSION` macros in `notmuch.h`, and the version loaded at runtime from the
+//! file name of the shared library. Both are versions of the library ABI, e.g. `5.6.0`, not of
+//! the notmuch release.
+//!
+//! Optional features of the library and the database are checked with `built_with` and
+//! `Database::has_feature`.
+use notmuch_sys::{notmuch_built_with, notmuch_status_to_string};
+use std::{
+    ffi::{CStr, CString, OsStr},
+    fmt::Display,
+    fs,
+    os::{raw::c_void, unix::ffi::OsStrExt},
+    path::Path,
+};
+
+/// Version of libnotmuch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub micro: u32,
+}
+
+impl Version {
+    /// Parse a `major.minor.micro` version.
+    fn parse(version: &str) -> Option<Self> {
+        let mut components = version.split('.').map(|c| c.parse().ok());
+
+        let version = Version {
+            major: components.next()??,
+            minor: components.next()??,
+            micro: components.next()??,
+        };
+
+        match components.next() {
+            None => Some(version),
+            Some(_) => None,
+        }
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.micro)
+    }
+}
+
+/// Returns the version of libnotmuch the crate was compiled against.
+///
+/// This is `0.0.0` if `notmuch.h` wasn't found when building, see `NOTMUCH_API` in the
+/// `notmuch-sys` README.
+pub fn compiled() -> Version {
+    Version::parse(env!("NOTMUCH_LIB_VERSION")).expect("Invalid libnotmuch version")
+}
+
+/// Returns the version of the libnotmuch shared library loaded at runtime.
+///
+/// This is read from the name of the library file, e.g. `libnotmuch.so.5.6.0`, so it's `None`
+/// if libnotmuch is linked statically or the file isn't named after its full version.
+pub fn runtime() -> Option<Version> {
+    let mut info: libc::Dl_info = unsafe { std::mem::zeroed() };
+    let symbol = notmuch_status_to_string as *const c_void;
+
+    if unsafe { libc::dladdr(symbol, &mut info) } == 0 || info.dli_fname.is_null() {
+        return None;
+    }
+
+    let path = unsafe { CStr::from_ptr(info.dli_fname) };
+    // The path loaded is usually a symlink named after the major version only.
+    let path = fs::canonicalize(Path::new(OsStr::from_bytes(path.to_bytes()))).ok()?;
+    let name = path.file_name()?.to_str()?;
+
+    if !name.starts_with("libnotmuch.") {
+        return None;
+    }
+
+    Version::parse(&name[name.find(".so.")? + 4..])
+}
+
+/// Whether libnotmuch was built with the optional feature `name`, such as `sexp_queries`.
+///
+/// Unknown features are reported as missing.
+pub fn built_with(name: &str) -> bool {
+    let name = CString::new(name).unwrap();
+    unsafe { notmuch_built_with(name.as_ptr()) != 0 }
+}
+
+/// Optional features of libnotmuch or of a database, checked with `Database::has_feature`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// S-expression queries, see `QuerySyntax::Sexp`. Depends on how libnotmuch was built.
+    SexpQueries,
+    /// Configuration stored in the database and merged with the configuration file when opening.
+    /// Requires notmuch 0.32 or later.
+    IndexedConfig,
+    /// Message properties, used for example to store session keys, and the `property:` prefix.
+    Properties,
+    /// Revision numbers for `lastmod:` queries and `Database::revision`. Depends on the database
+    /// format, so older databases have to be upgraded.
+    Lastmod,
+}
+
+impl Capability {
+    /// Whether the capability is available with a database of format version `format_version`,
+    /// see `Database::version`.
+    pub fn available_with(&self, format_version: u32) -> bool {
+        match self {
+            Capability::SexpQueries => built_with("sexp_queries"),
+            Capability::IndexedConfig => cfg!(notmuch_0_32),
+            // Revisions were added in format version 3. Properties came later, so databases of
+            // older formats predate them.
+            Capability::Properties | Capability::Lastmod => format_version >= 3,
+        }
+    }
+}
@@ -0,0 +1,393 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Continuous indexing of the mail root using inotify.
+//!
+//! Requires the `watch` feature and notmuch 0.32 or later. Bursts of filesystem events, such as a
+//! mail client moving many messages at once, are collected until the mail root has been quiet for
+//! the debounce interval, then applied to the database like `notmuch new` would.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let db = Database::open(&path, OpenMode::ReadWrite)?;
+//! let (tx, rx) = std::sync::mpsc::channel();
+//! let mut watcher = Watcher::new(&db)?;
+//! let stop = watcher.stop_handle();
+//!
+//! std::thread::spawn(move || {
+//!     for event in rx.iter().take(10) {
+//!         println!("{:?}", event);
+//!     }
+//!     stop.stop();
+//! });
+//!
+//! watcher.run(&db, &tx)?;
+//! ```
+use crate::{
+    db::Database,
+    new::{self, NewSummary, Scanner},
+    status::Status,
+};
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    ffi::OsString,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Default time the mail root has to be quiet before changes are indexed.
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How often to check for new events and for being stopped while the mail root is quiet.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Change made to the database by a `Watcher`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEvent {
+    /// A mail file was indexed.
+    Added(PathBuf),
+    /// A mail file was removed from the database.
+    Removed(PathBuf),
+    /// A mail file was moved within the mail root.
+    Renamed { from: PathBuf, to: PathBuf },
+    /// Changes to whole directories, or more changes than inotify could queue, required
+    /// rescanning the mail root.
+    Rescanned(NewSummary),
+}
+
+/// Error stopping a `Watcher`.
+#[derive(Debug)]
+pub enum WatchError {
+    /// Reading filesystem events failed.
+    Io(io::Error),
+    /// Updating the database failed.
+    Status(Status),
+}
+
+impl Error for WatchError {}
+
+impl Display for WatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchError::Io(e) => write!(f, "{}", e),
+            WatchError::Status(st) => write!(f, "{}", st),
+        }
+    }
+}
+
+impl From<io::Error> for WatchError {
+    fn from(e: io::Error) -> Self {
+        WatchError::Io(e)
+    }
+}
+
+impl From<Status> for WatchError {
+    fn from(st: Status) -> Self {
+        WatchError::Status(st)
+    }
+}
+
+/// Filesystem event relevant for indexing, with the affected path resolved.
+struct RawEvent {
+    mask: EventMask,
+    cookie: u32,
+    path: PathBuf,
+}
+
+/// Handle to stop a running `Watcher`, e.g. from another thread.
+#[derive(Debug, Clone)]
+pub struct StopHandle(Arc<AtomicBool>);
+
+impl StopHandle {
+    /// Make `Watcher::run` return once it is done with the changes it is indexing.
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Watches every directory below the mail root for new, removed and renamed mail files.
+pub struct Watcher {
+    inotify: Inotify,
+    dirs: HashMap<WatchDescriptor, PathBuf>,
+    debounce: Duration,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Watcher {
+    /// Start watching the mail root configured for `db`.
+    ///
+    /// Directories excluded by `new.ignore`, the `.notmuch` directory and maildir `tmp`
+    /// directories are not watched.
+    pub fn new(db: &Database) -> Result<Self, WatchError> {
+        let scanner = Scanner::new(db)?;
+        let mut watcher = Watcher {
+            inotify: Inotify::init()?,
+            dirs: HashMap::new(),
+            debounce: DEFAULT_DEBOUNCE,
+            stopped: Arc::new(AtomicBool::new(false)),
+        };
+
+        watcher.watch_tree(&scanner, &scanner.mail_root)?;
+
+        Ok(watcher)
+    }
+
+    /// Set how long the mail root has to be quiet before collected changes are indexed.
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Returns a handle to stop `run`.
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle(self.stopped.clone())
+    }
+
+    /// Index changes as they happen, reporting each one over `events`.
+    ///
+    /// This blocks until stopped with a `StopHandle` or an error occurs. It also returns when
+    /// the receiving end of `events` has been dropped, which is only noticed when reporting the
+    /// next change. The database must be opened in read-write mode.
+    pub fn run(&mut self, db: &Database, events: &Sender<WatchEvent>) -> Result<(), WatchError> {
+        while let Some(batch) = self.next_batch()? {
+            for event in self.apply(db, batch)? {
+                if events.send(event).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add watches for `path` and every directory below it.
+    fn watch_tree(&mut self, scanner: &Scanner, path: &Path) -> io::Result<()> {
+        let mask = WatchMask::CLOSE_WRITE
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO
+            | WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::ONLYDIR;
+        let wd = self.inotify.watches().add(path, mask)?;
+        self.dirs.insert(wd, path.to_path_buf());
+
+        let mut subdirs = BTreeSet::new();
+
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let name = entry.file_name();
+
+            if name == ".notmuch" || scanner.is_ignored(&name, &entry.path()) {
+                continue;
+            }
+
+            if matches!(fs::metadata(entry.path()), Ok(m) if m.is_dir()) {
+                subdirs.insert(name);
+            }
+        }
+
+        // Mail is only ever delivered to `tmp` temporarily.
+        if subdirs.contains(&OsString::from("cur")) && subdirs.contains(&OsString::from("new")) {
+            subdirs.remove(&OsString::from("tmp"));
+        }
+
+        for name in subdirs {
+            self.watch_tree(scanner, &path.join(name))?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the watches for `path` and every directory below it.
+    fn unwatch_tree(&mut self, path: &Path) {
+        let gone: Vec<WatchDescriptor> = self
+            .dirs
+            .iter()
+            .filter(|(_, dir)| dir.starts_with(path))
+            .map(|(wd, _)| wd.clone())
+            .collect();
+
+        for wd in gone {
+            self.dirs.remove(&wd);
+            // Deleted directories already lost their watch.
+            let _ = self.inotify.watches().remove(wd);
+        }
+    }
+
+    /// Wait until events arrive, then collect events until none arrived for the debounce
+    /// interval.
+    ///
+    /// Returns `None` if stopped while waiting.
+    fn next_batch(&mut self) -> io::Result<Option<Vec<RawEvent>>> {
+        let mut buffer = [0; 4096];
+        let mut batch = Vec::new();
+
+        while batch.is_empty() {
+            if self.stopped.load(Ordering::SeqCst) {
+                return Ok(None);
+            }
+
+            match self.inotify.read_events(&mut buffer) {
+                Ok(events) => self.collect(events, &mut batch),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut last_event = Instant::now();
+
+        while last_event.elapsed() < self.debounce {
+            match self.inotify.read_events(&mut buffer) {
+                Ok(events) => {
+                    self.collect(events, &mut batch);
+                    last_event = Instant::now();
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    let remaining = self.debounce.saturating_sub(last_event.elapsed());
+                    thread::sleep(remaining.min(POLL_INTERVAL));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Some(batch))
+    }
+
+    fn collect<'a>(
+        &mut self,
+        events: impl Iterator<Item = inotify::Event<&'a std::ffi::OsStr>>,
+        batch: &mut Vec<RawEvent>,
+    ) {
+        for event in events {
+            if event.mask.contains(EventMask::IGNORED) {
+                self.dirs.remove(&event.wd);
+                continue;
+            }
+
+            let path = match (self.dirs.get(&event.wd), event.name) {
+                (Some(dir), Some(name)) => dir.join(name),
+                (Some(dir), None) => dir.clone(),
+                (None, _) => PathBuf::new(),
+            };
+
+            batch.push(RawEvent {
+                mask: event.mask,
+                cookie: event.cookie,
+                path,
+            });
+        }
+    }
+
+    /// Apply the net effect of `batch` to the database.
+    fn apply(
+        &mut self,
+        db: &Database,
+        batch: Vec<RawEvent>,
+    ) -> Result<Vec<WatchEvent>, WatchError> {
+        let mut scanner = Scanner::new(db)?;
+
+        // Directory changes and queue overflows are left to a full, mtime-based rescan.
+        let rescan = batch.iter().any(|event| {
+            event.mask.contains(EventMask::ISDIR) || event.mask.contains(EventMask::Q_OVERFLOW)
+        });
+
+        if rescan {
+            for event in &batch {
+                let is_gone_dir = event.mask.contains(EventMask::ISDIR)
+                    && (event.mask.contains(EventMask::MOVED_FROM)
+                        || event.mask.contains(EventMask::DELETE));
+
+                if is_gone_dir {
+                    self.unwatch_tree(&event.path);
+                }
+            }
+
+            // Watching an already watched directory again only updates its path.
+            let mail_root = scanner.mail_root.clone();
+            self.watch_tree(&scanner, &mail_root)?;
+
+            return Ok(vec![WatchEvent::Rescanned(new::run(db)?)]);
+        }
+
+        let mut moved_from = HashMap::new();
+        let mut renames = Vec::new();
+        let mut touched = Vec::new();
+
+        for event in batch {
+            // Files are picked up once they are completely written or moved into place.
+            if event.mask.contains(EventMask::CREATE) {
+                continue;
+            }
+
+            if event.mask.contains(EventMask::MOVED_FROM) {
+                moved_from.insert(event.cookie, event.path);
+            } else if event.mask.contains(EventMask::MOVED_TO) {
+                match moved_from.remove(&event.cookie) {
+                    Some(from) => renames.push((from, event.path)),
+                    None => touched.push(event.path),
+                }
+            } else {
+                touched.push(event.path);
+            }
+        }
+
+        // Files moved out of the mail root are gone as far as the database is concerned.
+        touched.extend(moved_from.into_values());
+
+        let mut events = Vec::new();
+
+        for (from, to) in renames {
+            let from_ignored = is_ignored(&scanner, &from);
+            let to_ignored = is_ignored(&scanner, &to);
+
+            // New files are added before old ones are removed, so the message is kept.
+            if !to_ignored && to.is_file() && scanner.add_file(&to)? {
+                if from_ignored {
+                    events.push(WatchEvent::Added(to));
+                } else {
+                    scanner.remove_file(&from)?;
+                    events.push(WatchEvent::Renamed { from, to });
+                }
+            } else if !from_ignored {
+                scanner.remove_file(&from)?;
+                events.push(WatchEvent::Removed(from));
+            }
+        }
+
+        let touched: BTreeSet<PathBuf> = touched.into_iter().collect();
+
+        for path in touched {
+            if is_ignored(&scanner, &path) {
+                continue;
+            }
+
+            if path.is_file() {
+                if db.find_message_by_filename(&path)?.is_none() && scanner.add_file(&path)? {
+                    events.push(WatchEvent::Added(path));
+                }
+            } else if db.find_message_by_filename(&path)?.is_some() {
+                scanner.remove_file(&path)?;
+                events.push(WatchEvent::Removed(path));
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// Whether changes to the file at `path` should not be indexed.
+fn is_ignored(scanner: &Scanner, path: &Path) -> bool {
+    match path.file_name() {
+        Some(name) => name == ".notmuch" || scanner.is_ignored(name, path),
+        None => true,
+    }
+}
@@ -1,21 +1,223 @@
 /// Build script for rust library.
+#[cfg(feature = "bindgen")]
 extern crate bindgen;
 
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `cfg` flags for API additions, each detected by a symbol first declared in `notmuch.h` of
+/// that release.
+const FEATURES: [(&str, &str); 3] = [
+    ("notmuch_0_32", "notmuch_database_open_with_config"),
+    ("notmuch_0_33", "NOTMUCH_STATUS_NO_MAIL_ROOT"),
+    ("notmuch_0_34", "notmuch_query_create_with_syntax"),
+];
+
+/// Locations searched for `notmuch.h` if pkg-config doesn't know about notmuch.
+const DEFAULT_INCLUDE_DIRS: [&str; 2] = ["/usr/include", "/usr/local/include"];
+
+/// pkg-config packages of the libraries libnotmuch links against, needed when linking it
+/// statically.
+const STATIC_DEPENDENCIES: [&str; 5] = ["xapian-core", "gmime-3.0", "glib-2.0", "talloc", "zlib"];
 
 fn main() {
-    println!("cargo:rustc-link-lib=notmuch");
     println!("cargo:rerun-if-changed=wrapper.h");
+    for var in &[
+        "NOTMUCH_LIB_DIR",
+        "NOTMUCH_INCLUDE_DIR",
+        "NOTMUCH_STATIC",
+        "NOTMUCH_API",
+    ] {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+
+    let include_dirs = link();
+
+    let (cfgs, version) = match find_header(&include_dirs) {
+        Some(header) => {
+            println!("cargo:rerun-if-changed={}", header.display());
+
+            let source = fs::read_to_string(&header).expect("Unable to read notmuch.h");
+            let cfgs: Vec<&str> = FEATURES
+                .iter()
+                .filter(|(_, symbol)| source.contains(symbol))
+                .map(|(cfg, _)| *cfg)
+                .collect();
+
+            (cfgs, header_version(&source))
+        }
+        None => assumed_features(),
+    };
+
+    for (cfg, _) in &FEATURES {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+    for cfg in &cfgs {
+        println!("cargo:rustc-cfg={}", cfg);
+    }
+
+    // Passed on to dependent crates as `DEP_NOTMUCH_*`.
+    let known: Vec<&str> = FEATURES.iter().map(|(cfg, _)| *cfg).collect();
+    println!("cargo:known_cfgs={}", known.join(","));
+    println!("cargo:cfgs={}", cfgs.join(","));
+    println!("cargo:lib_version={}", version);
 
+    // Checked-in bindings for the newest API the installed header provides. Also used by
+    // `tests/bindings.rs` to compare against freshly generated bindings. Releases before the
+    // oldest flag get the oldest bindings, whose extra declarations are left unused as the safe
+    // bindings compile out everything behind the flags.
+    let newest = cfgs.last().unwrap_or(&FEATURES[0].0);
+    let checked_in = format!("src/bindings/{}.rs", newest);
+    println!("cargo:rustc-env=NOTMUCH_CHECKED_IN_BINDINGS={}", checked_in);
+
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("bindings.rs");
+    write_bindings(&include_dirs, Path::new(&checked_in), &out_path);
+}
+
+/// Generate bindings from the installed `notmuch.h`.
+#[cfg(feature = "bindgen")]
+fn write_bindings(include_dirs: &[PathBuf], _checked_in: &Path, out_path: &Path) {
     let bindings = bindgen::Builder::default()
         .header("wrapper.h")
+        .clang_args(
+            include_dirs
+                .iter()
+                .map(|dir| format!("-I{}", dir.display())),
+        )
+        .whitelist_function("notmuch_.*")
+        .whitelist_type("_?notmuch_.*")
+        .whitelist_var("(LIB)?NOTMUCH_.*")
+        .generate_comments(false)
         .parse_callbacks(Box::new(bindgen::CargoCallbacks))
         .generate()
         .expect("Unable to generate bindings");
 
-    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
     bindings
-        .write_to_file(out_path.join("bindings.rs"))
+        .write_to_file(out_path)
         .expect("Couldn't write bindings!");
 }
+
+/// Use the checked-in bindings, so libclang isn't needed to build.
+#[cfg(not(feature = "bindgen"))]
+fn write_bindings(_include_dirs: &[PathBuf], checked_in: &Path, out_path: &Path) {
+    println!("cargo:rerun-if-changed={}", checked_in.display());
+
+    fs::copy(checked_in, out_path).expect("Couldn't copy bindings!");
+}
+
+/// Without `notmuch.h`, bindgen has nothing to generate bindings from.
+#[cfg(feature = "bindgen")]
+fn assumed_features() -> (Vec<&'static str>, String) {
+    panic!("Unable to find notmuch.h, set NOTMUCH_INCLUDE_DIR if it isn't installed system-wide");
+}
+
+/// `cfg` flags and library version to build the checked-in bindings with if `notmuch.h` isn't
+/// installed, e.g. when cross-compiling.
+///
+/// `NOTMUCH_API` names the flag of the newest release to assume, e.g. `notmuch_0_34`, enabling
+/// it and all older ones. Otherwise, no flag is enabled. The library version is unknown, so it's
+/// reported as `0.0.0`.
+#[cfg(not(feature = "bindgen"))]
+fn assumed_features() -> (Vec<&'static str>, String) {
+    let cfgs: Vec<&str> = match env::var("NOTMUCH_API") {
+        Ok(newest) => {
+            let count = FEATURES
+                .iter()
+                .position(|(cfg, _)| *cfg == newest)
+                .unwrap_or_else(|| panic!("Unknown NOTMUCH_API {}", newest));
+
+            FEATURES[..=count].iter().map(|(cfg, _)| *cfg).collect()
+        }
+        Err(_) => Vec::new(),
+    };
+
+    println!(
+        "cargo:warning=Unable to find notmuch.h, assuming the API of {}",
+        cfgs.last().map_or("notmuch before 0.32", |cfg| cfg)
+    );
+
+    (cfgs, "0.0.0".to_string())
+}
+
+/// Emit the linker flags for libnotmuch, returning the directories to search for `notmuch.h`.
+///
+/// `NOTMUCH_LIB_DIR` and `NOTMUCH_INCLUDE_DIR` point at a libnotmuch outside of pkg-config's
+/// reach, and setting `NOTMUCH_STATIC` links it statically, along with its dependencies.
+fn link() -> Vec<PathBuf> {
+    let statik = matches!(env::var_os("NOTMUCH_STATIC"), Some(value) if value != "0");
+    let mut include_dirs: Vec<PathBuf> = env::var_os("NOTMUCH_INCLUDE_DIR")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+
+    match env::var_os("NOTMUCH_LIB_DIR") {
+        Some(lib_dir) => {
+            println!(
+                "cargo:rustc-link-search=native={}",
+                Path::new(&lib_dir).display()
+            );
+            link_notmuch(statik);
+        }
+        // pkg-config also emits the linker flags.
+        None => match pkg_config::Config::new().statik(statik).probe("notmuch") {
+            Ok(lib) => include_dirs.extend(lib.include_paths),
+            Err(_) => link_notmuch(statik),
+        },
+    }
+
+    if statik {
+        for dependency in &STATIC_DEPENDENCIES {
+            if let Err(err) = pkg_config::Config::new().statik(true).probe(dependency) {
+                panic!(
+                    "Unable to find {} for linking libnotmuch statically: {}",
+                    dependency, err
+                );
+            }
+        }
+
+        // Xapian is written in C++.
+        match env::var("CARGO_CFG_TARGET_OS").as_deref() {
+            Ok("macos") | Ok("ios") | Ok("freebsd") => println!("cargo:rustc-link-lib=c++"),
+            _ => println!("cargo:rustc-link-lib=stdc++"),
+        }
+    }
+
+    include_dirs
+}
+
+fn link_notmuch(statik: bool) {
+    let kind = if statik { "static" } else { "dylib" };
+    println!("cargo:rustc-link-lib={}=notmuch", kind);
+}
+
+fn find_header(include_dirs: &[PathBuf]) -> Option<PathBuf> {
+    include_dirs
+        .iter()
+        .map(PathBuf::as_path)
+        .chain(DEFAULT_INCLUDE_DIRS.iter().map(Path::new))
+        .map(|dir| dir.join("notmuch.h"))
+        .find(|header| header.exists())
+}
+
+/// Library version from the `LIBNOTMUCH_*_VERSION` macros, e.g. `5.4.0`.
+///
+/// This is the version of the library ABI, not the notmuch release.
+fn header_version(source: &str) -> String {
+    let component = |name: &str| {
+        source
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("#define"))
+            .filter_map(|line| line.trim().strip_prefix(name))
+            .filter_map(|value| value.trim().parse::<u32>().ok())
+            .next()
+            .unwrap_or(0)
+    };
+
+    format!(
+        "{}.{}.{}",
+        component("LIBNOTMUCH_MAJOR_VERSION"),
+        component("LIBNOTMUCH_MINOR_VERSION"),
+        component("LIBNOTMUCH_MICRO_VERSION")
+    )
+}
@@ -0,0 +1,843 @@
+/* automatically generated by rust-bindgen */
+
+pub type __time_t = ::std::os::raw::c_long;
+pub type time_t = __time_t;
+pub const LIBNOTMUCH_MAJOR_VERSION: u32 = 5;
+pub const LIBNOTMUCH_MINOR_VERSION: u32 = 5;
+pub const LIBNOTMUCH_MICRO_VERSION: u32 = 0;
+pub type notmuch_bool_t = ::std::os::raw::c_int;
+pub const _notmuch_status_NOTMUCH_STATUS_SUCCESS: _notmuch_status = 0;
+pub const _notmuch_status_NOTMUCH_STATUS_OUT_OF_MEMORY: _notmuch_status = 1;
+pub const _notmuch_status_NOTMUCH_STATUS_READ_ONLY_DATABASE: _notmuch_status = 2;
+pub const _notmuch_status_NOTMUCH_STATUS_XAPIAN_EXCEPTION: _notmuch_status = 3;
+pub const _notmuch_status_NOTMUCH_STATUS_FILE_ERROR: _notmuch_status = 4;
+pub const _notmuch_status_NOTMUCH_STATUS_FILE_NOT_EMAIL: _notmuch_status = 5;
+pub const _notmuch_status_NOTMUCH_STATUS_DUPLICATE_MESSAGE_ID: _notmuch_status = 6;
+pub const _notmuch_status_NOTMUCH_STATUS_NULL_POINTER: _notmuch_status = 7;
+pub const _notmuch_status_NOTMUCH_STATUS_TAG_TOO_LONG: _notmuch_status = 8;
+pub const _notmuch_status_NOTMUCH_STATUS_UNBALANCED_FREEZE_THAW: _notmuch_status = 9;
+pub const _notmuch_status_NOTMUCH_STATUS_UNBALANCED_ATOMIC: _notmuch_status = 10;
+pub const _notmuch_status_NOTMUCH_STATUS_UNSUPPORTED_OPERATION: _notmuch_status = 11;
+pub const _notmuch_status_NOTMUCH_STATUS_UPGRADE_REQUIRED: _notmuch_status = 12;
+pub const _notmuch_status_NOTMUCH_STATUS_PATH_ERROR: _notmuch_status = 13;
+pub const _notmuch_status_NOTMUCH_STATUS_IGNORED: _notmuch_status = 14;
+pub const _notmuch_status_NOTMUCH_STATUS_ILLEGAL_ARGUMENT: _notmuch_status = 15;
+pub const _notmuch_status_NOTMUCH_STATUS_MALFORMED_CRYPTO_PROTOCOL: _notmuch_status = 16;
+pub const _notmuch_status_NOTMUCH_STATUS_FAILED_CRYPTO_CONTEXT_CREATION: _notmuch_status = 17;
+pub const _notmuch_status_NOTMUCH_STATUS_UNKNOWN_CRYPTO_PROTOCOL: _notmuch_status = 18;
+pub const _notmuch_status_NOTMUCH_STATUS_NO_CONFIG: _notmuch_status = 19;
+pub const _notmuch_status_NOTMUCH_STATUS_NO_DATABASE: _notmuch_status = 20;
+pub const _notmuch_status_NOTMUCH_STATUS_DATABASE_EXISTS: _notmuch_status = 21;
+pub const _notmuch_status_NOTMUCH_STATUS_BAD_QUERY_SYNTAX: _notmuch_status = 22;
+pub const _notmuch_status_NOTMUCH_STATUS_NO_MAIL_ROOT: _notmuch_status = 23;
+pub const _notmuch_status_NOTMUCH_STATUS_LAST_STATUS: _notmuch_status = 24;
+pub type _notmuch_status = u32;
+pub use self::_notmuch_status as notmuch_status_t;
+extern "C" {
+    pub fn notmuch_status_to_string(status: notmuch_status_t) -> *const ::std::os::raw::c_char;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_database {
+    _unused: [u8; 0],
+}
+pub type notmuch_database_t = _notmuch_database;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_query {
+    _unused: [u8; 0],
+}
+pub type notmuch_query_t = _notmuch_query;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_threads {
+    _unused: [u8; 0],
+}
+pub type notmuch_threads_t = _notmuch_threads;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_thread {
+    _unused: [u8; 0],
+}
+pub type notmuch_thread_t = _notmuch_thread;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_messages {
+    _unused: [u8; 0],
+}
+pub type notmuch_messages_t = _notmuch_messages;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_message {
+    _unused: [u8; 0],
+}
+pub type notmuch_message_t = _notmuch_message;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_tags {
+    _unused: [u8; 0],
+}
+pub type notmuch_tags_t = _notmuch_tags;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_directory {
+    _unused: [u8; 0],
+}
+pub type notmuch_directory_t = _notmuch_directory;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_filenames {
+    _unused: [u8; 0],
+}
+pub type notmuch_filenames_t = _notmuch_filenames;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_config_list {
+    _unused: [u8; 0],
+}
+pub type notmuch_config_list_t = _notmuch_config_list;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_config_values {
+    _unused: [u8; 0],
+}
+pub type notmuch_config_values_t = _notmuch_config_values;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_config_pairs {
+    _unused: [u8; 0],
+}
+pub type notmuch_config_pairs_t = _notmuch_config_pairs;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_indexopts {
+    _unused: [u8; 0],
+}
+pub type notmuch_indexopts_t = _notmuch_indexopts;
+extern "C" {
+    pub fn notmuch_database_create(
+        path: *const ::std::os::raw::c_char,
+        database: *mut *mut notmuch_database_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_create_verbose(
+        path: *const ::std::os::raw::c_char,
+        database: *mut *mut notmuch_database_t,
+        error_message: *mut *mut ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+pub const notmuch_database_mode_t_NOTMUCH_DATABASE_MODE_READ_ONLY: notmuch_database_mode_t = 0;
+pub const notmuch_database_mode_t_NOTMUCH_DATABASE_MODE_READ_WRITE: notmuch_database_mode_t = 1;
+pub type notmuch_database_mode_t = u32;
+extern "C" {
+    pub fn notmuch_database_open(
+        path: *const ::std::os::raw::c_char,
+        mode: notmuch_database_mode_t,
+        database: *mut *mut notmuch_database_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_open_verbose(
+        path: *const ::std::os::raw::c_char,
+        mode: notmuch_database_mode_t,
+        database: *mut *mut notmuch_database_t,
+        error_message: *mut *mut ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_open_with_config(
+        database_path: *const ::std::os::raw::c_char,
+        mode: notmuch_database_mode_t,
+        config_path: *const ::std::os::raw::c_char,
+        profile: *const ::std::os::raw::c_char,
+        database: *mut *mut notmuch_database_t,
+        error_message: *mut *mut ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_load_config(
+        database_path: *const ::std::os::raw::c_char,
+        config_path: *const ::std::os::raw::c_char,
+        profile: *const ::std::os::raw::c_char,
+        database: *mut *mut notmuch_database_t,
+        error_message: *mut *mut ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_create_with_config(
+        database_path: *const ::std::os::raw::c_char,
+        config_path: *const ::std::os::raw::c_char,
+        profile: *const ::std::os::raw::c_char,
+        database: *mut *mut notmuch_database_t,
+        error_message: *mut *mut ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_status_string(
+        notmuch: *const notmuch_database_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_database_close(database: *mut notmuch_database_t) -> notmuch_status_t;
+}
+pub type notmuch_compact_status_cb_t = ::std::option::Option<
+    unsafe extern "C" fn(
+        message: *const ::std::os::raw::c_char,
+        closure: *mut ::std::os::raw::c_void,
+    ),
+>;
+extern "C" {
+    pub fn notmuch_database_compact(
+        path: *const ::std::os::raw::c_char,
+        backup_path: *const ::std::os::raw::c_char,
+        status_cb: notmuch_compact_status_cb_t,
+        closure: *mut ::std::os::raw::c_void,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_compact_db(
+        database: *mut notmuch_database_t,
+        backup_path: *const ::std::os::raw::c_char,
+        status_cb: notmuch_compact_status_cb_t,
+        closure: *mut ::std::os::raw::c_void,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_destroy(database: *mut notmuch_database_t) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_get_path(
+        database: *mut notmuch_database_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_database_get_version(
+        database: *mut notmuch_database_t,
+    ) -> ::std::os::raw::c_uint;
+}
+extern "C" {
+    pub fn notmuch_database_needs_upgrade(database: *mut notmuch_database_t) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_database_upgrade(
+        database: *mut notmuch_database_t,
+        progress_notify: ::std::option::Option<
+            unsafe extern "C" fn(closure: *mut ::std::os::raw::c_void, progress: f64),
+        >,
+        closure: *mut ::std::os::raw::c_void,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_begin_atomic(notmuch: *mut notmuch_database_t) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_end_atomic(notmuch: *mut notmuch_database_t) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_get_revision(
+        notmuch: *mut notmuch_database_t,
+        uuid: *mut *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_ulong;
+}
+extern "C" {
+    pub fn notmuch_database_reopen(
+        db: *mut notmuch_database_t,
+        mode: notmuch_database_mode_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_get_directory(
+        database: *mut notmuch_database_t,
+        path: *const ::std::os::raw::c_char,
+        directory: *mut *mut notmuch_directory_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_index_file(
+        database: *mut notmuch_database_t,
+        filename: *const ::std::os::raw::c_char,
+        indexopts: *mut notmuch_indexopts_t,
+        message: *mut *mut notmuch_message_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_add_message(
+        database: *mut notmuch_database_t,
+        filename: *const ::std::os::raw::c_char,
+        message: *mut *mut notmuch_message_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_remove_message(
+        database: *mut notmuch_database_t,
+        filename: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_find_message(
+        database: *mut notmuch_database_t,
+        message_id: *const ::std::os::raw::c_char,
+        message: *mut *mut notmuch_message_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_find_message_by_filename(
+        notmuch: *mut notmuch_database_t,
+        filename: *const ::std::os::raw::c_char,
+        message: *mut *mut notmuch_message_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_get_all_tags(db: *mut notmuch_database_t) -> *mut notmuch_tags_t;
+}
+extern "C" {
+    pub fn notmuch_query_create(
+        database: *mut notmuch_database_t,
+        query_string: *const ::std::os::raw::c_char,
+    ) -> *mut notmuch_query_t;
+}
+pub const notmuch_sort_t_NOTMUCH_SORT_OLDEST_FIRST: notmuch_sort_t = 0;
+pub const notmuch_sort_t_NOTMUCH_SORT_NEWEST_FIRST: notmuch_sort_t = 1;
+pub const notmuch_sort_t_NOTMUCH_SORT_MESSAGE_ID: notmuch_sort_t = 2;
+pub const notmuch_sort_t_NOTMUCH_SORT_UNSORTED: notmuch_sort_t = 3;
+pub type notmuch_sort_t = u32;
+extern "C" {
+    pub fn notmuch_query_get_query_string(
+        query: *const notmuch_query_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_query_get_database(query: *const notmuch_query_t) -> *mut notmuch_database_t;
+}
+pub const notmuch_exclude_t_NOTMUCH_EXCLUDE_FLAG: notmuch_exclude_t = 0;
+pub const notmuch_exclude_t_NOTMUCH_EXCLUDE_TRUE: notmuch_exclude_t = 1;
+pub const notmuch_exclude_t_NOTMUCH_EXCLUDE_FALSE: notmuch_exclude_t = 2;
+pub const notmuch_exclude_t_NOTMUCH_EXCLUDE_ALL: notmuch_exclude_t = 3;
+pub type notmuch_exclude_t = u32;
+extern "C" {
+    pub fn notmuch_query_set_omit_excluded(
+        query: *mut notmuch_query_t,
+        omit_excluded: notmuch_exclude_t,
+    );
+}
+extern "C" {
+    pub fn notmuch_query_set_sort(query: *mut notmuch_query_t, sort: notmuch_sort_t);
+}
+extern "C" {
+    pub fn notmuch_query_get_sort(query: *const notmuch_query_t) -> notmuch_sort_t;
+}
+extern "C" {
+    pub fn notmuch_query_add_tag_exclude(
+        query: *mut notmuch_query_t,
+        tag: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_query_search_threads(
+        query: *mut notmuch_query_t,
+        out: *mut *mut notmuch_threads_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_query_search_messages(
+        query: *mut notmuch_query_t,
+        out: *mut *mut notmuch_messages_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_query_destroy(query: *mut notmuch_query_t);
+}
+extern "C" {
+    pub fn notmuch_threads_valid(threads: *mut notmuch_threads_t) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_threads_get(threads: *mut notmuch_threads_t) -> *mut notmuch_thread_t;
+}
+extern "C" {
+    pub fn notmuch_threads_move_to_next(threads: *mut notmuch_threads_t);
+}
+extern "C" {
+    pub fn notmuch_threads_destroy(threads: *mut notmuch_threads_t);
+}
+extern "C" {
+    pub fn notmuch_query_count_messages(
+        query: *mut notmuch_query_t,
+        count: *mut ::std::os::raw::c_uint,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_query_count_threads(
+        query: *mut notmuch_query_t,
+        count: *mut ::std::os::raw::c_uint,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_thread_get_thread_id(
+        thread: *mut notmuch_thread_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_thread_get_total_messages(
+        thread: *mut notmuch_thread_t,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn notmuch_thread_get_total_files(thread: *mut notmuch_thread_t) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn notmuch_thread_get_toplevel_messages(
+        thread: *mut notmuch_thread_t,
+    ) -> *mut notmuch_messages_t;
+}
+extern "C" {
+    pub fn notmuch_thread_get_messages(thread: *mut notmuch_thread_t) -> *mut notmuch_messages_t;
+}
+extern "C" {
+    pub fn notmuch_thread_get_matched_messages(
+        thread: *mut notmuch_thread_t,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn notmuch_thread_get_authors(
+        thread: *mut notmuch_thread_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_thread_get_subject(
+        thread: *mut notmuch_thread_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_thread_get_oldest_date(thread: *mut notmuch_thread_t) -> time_t;
+}
+extern "C" {
+    pub fn notmuch_thread_get_newest_date(thread: *mut notmuch_thread_t) -> time_t;
+}
+extern "C" {
+    pub fn notmuch_thread_get_tags(thread: *mut notmuch_thread_t) -> *mut notmuch_tags_t;
+}
+extern "C" {
+    pub fn notmuch_thread_destroy(thread: *mut notmuch_thread_t);
+}
+extern "C" {
+    pub fn notmuch_messages_valid(messages: *mut notmuch_messages_t) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_messages_get(messages: *mut notmuch_messages_t) -> *mut notmuch_message_t;
+}
+extern "C" {
+    pub fn notmuch_messages_move_to_next(messages: *mut notmuch_messages_t);
+}
+extern "C" {
+    pub fn notmuch_messages_destroy(messages: *mut notmuch_messages_t);
+}
+extern "C" {
+    pub fn notmuch_messages_collect_tags(messages: *mut notmuch_messages_t) -> *mut notmuch_tags_t;
+}
+extern "C" {
+    pub fn notmuch_message_get_database(
+        message: *const notmuch_message_t,
+    ) -> *mut notmuch_database_t;
+}
+extern "C" {
+    pub fn notmuch_message_get_message_id(
+        message: *mut notmuch_message_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_message_get_thread_id(
+        message: *mut notmuch_message_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_message_get_replies(message: *mut notmuch_message_t) -> *mut notmuch_messages_t;
+}
+extern "C" {
+    pub fn notmuch_message_count_files(message: *mut notmuch_message_t) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn notmuch_message_get_filename(
+        message: *mut notmuch_message_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_message_get_filenames(
+        message: *mut notmuch_message_t,
+    ) -> *mut notmuch_filenames_t;
+}
+extern "C" {
+    pub fn notmuch_message_reindex(
+        message: *mut notmuch_message_t,
+        indexopts: *mut notmuch_indexopts_t,
+    ) -> notmuch_status_t;
+}
+pub const _notmuch_message_flag_NOTMUCH_MESSAGE_FLAG_MATCH: _notmuch_message_flag = 0;
+pub const _notmuch_message_flag_NOTMUCH_MESSAGE_FLAG_EXCLUDED: _notmuch_message_flag = 1;
+pub const _notmuch_message_flag_NOTMUCH_MESSAGE_FLAG_GHOST: _notmuch_message_flag = 2;
+pub type _notmuch_message_flag = u32;
+pub use self::_notmuch_message_flag as notmuch_message_flag_t;
+extern "C" {
+    pub fn notmuch_message_get_flag(
+        message: *mut notmuch_message_t,
+        flag: notmuch_message_flag_t,
+    ) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_message_get_flag_st(
+        message: *mut notmuch_message_t,
+        flag: notmuch_message_flag_t,
+        is_set: *mut notmuch_bool_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_set_flag(
+        message: *mut notmuch_message_t,
+        flag: notmuch_message_flag_t,
+        value: notmuch_bool_t,
+    );
+}
+extern "C" {
+    pub fn notmuch_message_get_date(message: *mut notmuch_message_t) -> time_t;
+}
+extern "C" {
+    pub fn notmuch_message_get_header(
+        message: *mut notmuch_message_t,
+        header: *const ::std::os::raw::c_char,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_message_get_tags(message: *mut notmuch_message_t) -> *mut notmuch_tags_t;
+}
+pub const NOTMUCH_TAG_MAX: u32 = 200;
+extern "C" {
+    pub fn notmuch_message_add_tag(
+        message: *mut notmuch_message_t,
+        tag: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_remove_tag(
+        message: *mut notmuch_message_t,
+        tag: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_remove_all_tags(message: *mut notmuch_message_t) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_maildir_flags_to_tags(
+        message: *mut notmuch_message_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_has_maildir_flag(
+        message: *mut notmuch_message_t,
+        flag: ::std::os::raw::c_char,
+    ) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_message_has_maildir_flag_st(
+        message: *mut notmuch_message_t,
+        flag: ::std::os::raw::c_char,
+        is_set: *mut notmuch_bool_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_tags_to_maildir_flags(
+        message: *mut notmuch_message_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_freeze(message: *mut notmuch_message_t) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_thaw(message: *mut notmuch_message_t) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_destroy(message: *mut notmuch_message_t);
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _notmuch_string_map_iterator {
+    _unused: [u8; 0],
+}
+pub type notmuch_message_properties_t = _notmuch_string_map_iterator;
+extern "C" {
+    pub fn notmuch_message_get_property(
+        message: *mut notmuch_message_t,
+        key: *const ::std::os::raw::c_char,
+        value: *mut *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_add_property(
+        message: *mut notmuch_message_t,
+        key: *const ::std::os::raw::c_char,
+        value: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_remove_property(
+        message: *mut notmuch_message_t,
+        key: *const ::std::os::raw::c_char,
+        value: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_remove_all_properties(
+        message: *mut notmuch_message_t,
+        key: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_remove_all_properties_with_prefix(
+        message: *mut notmuch_message_t,
+        prefix: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_get_properties(
+        message: *mut notmuch_message_t,
+        key: *const ::std::os::raw::c_char,
+        exact: notmuch_bool_t,
+    ) -> *mut notmuch_message_properties_t;
+}
+extern "C" {
+    pub fn notmuch_message_count_properties(
+        message: *mut notmuch_message_t,
+        key: *const ::std::os::raw::c_char,
+        count: *mut ::std::os::raw::c_uint,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_message_properties_valid(
+        properties: *mut notmuch_message_properties_t,
+    ) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_message_properties_move_to_next(properties: *mut notmuch_message_properties_t);
+}
+extern "C" {
+    pub fn notmuch_message_properties_key(
+        properties: *mut notmuch_message_properties_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_message_properties_value(
+        properties: *mut notmuch_message_properties_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_message_properties_destroy(properties: *mut notmuch_message_properties_t);
+}
+extern "C" {
+    pub fn notmuch_tags_valid(tags: *mut notmuch_tags_t) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_tags_get(tags: *mut notmuch_tags_t) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_tags_move_to_next(tags: *mut notmuch_tags_t);
+}
+extern "C" {
+    pub fn notmuch_tags_destroy(tags: *mut notmuch_tags_t);
+}
+extern "C" {
+    pub fn notmuch_directory_set_mtime(
+        directory: *mut notmuch_directory_t,
+        mtime: time_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_directory_get_mtime(directory: *mut notmuch_directory_t) -> time_t;
+}
+extern "C" {
+    pub fn notmuch_directory_get_child_files(
+        directory: *mut notmuch_directory_t,
+    ) -> *mut notmuch_filenames_t;
+}
+extern "C" {
+    pub fn notmuch_directory_get_child_directories(
+        directory: *mut notmuch_directory_t,
+    ) -> *mut notmuch_filenames_t;
+}
+extern "C" {
+    pub fn notmuch_directory_delete(directory: *mut notmuch_directory_t) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_directory_destroy(directory: *mut notmuch_directory_t);
+}
+extern "C" {
+    pub fn notmuch_filenames_valid(filenames: *mut notmuch_filenames_t) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_filenames_get(
+        filenames: *mut notmuch_filenames_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_filenames_move_to_next(filenames: *mut notmuch_filenames_t);
+}
+extern "C" {
+    pub fn notmuch_filenames_destroy(filenames: *mut notmuch_filenames_t);
+}
+extern "C" {
+    pub fn notmuch_database_set_config(
+        db: *mut notmuch_database_t,
+        key: *const ::std::os::raw::c_char,
+        value: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_get_config(
+        db: *mut notmuch_database_t,
+        key: *const ::std::os::raw::c_char,
+        value: *mut *mut ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_database_get_config_list(
+        db: *mut notmuch_database_t,
+        prefix: *const ::std::os::raw::c_char,
+        out: *mut *mut notmuch_config_list_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_config_list_valid(config_list: *mut notmuch_config_list_t) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_config_list_key(
+        config_list: *mut notmuch_config_list_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_config_list_value(
+        config_list: *mut notmuch_config_list_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_config_list_move_to_next(config_list: *mut notmuch_config_list_t);
+}
+extern "C" {
+    pub fn notmuch_config_list_destroy(config_list: *mut notmuch_config_list_t);
+}
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_FIRST: notmuch_config_key_t = 0;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_DATABASE_PATH: notmuch_config_key_t = 0;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_MAIL_ROOT: notmuch_config_key_t = 1;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_HOOK_DIR: notmuch_config_key_t = 2;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_BACKUP_DIR: notmuch_config_key_t = 3;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_EXCLUDE_TAGS: notmuch_config_key_t = 4;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_NEW_TAGS: notmuch_config_key_t = 5;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_NEW_IGNORE: notmuch_config_key_t = 6;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_SYNC_MAILDIR_FLAGS: notmuch_config_key_t = 7;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_PRIMARY_EMAIL: notmuch_config_key_t = 8;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_OTHER_EMAIL: notmuch_config_key_t = 9;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_USER_NAME: notmuch_config_key_t = 10;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_AUTOCOMMIT: notmuch_config_key_t = 11;
+pub const notmuch_config_key_t_NOTMUCH_CONFIG_LAST: notmuch_config_key_t = 12;
+pub type notmuch_config_key_t = u32;
+extern "C" {
+    pub fn notmuch_config_get(
+        notmuch: *mut notmuch_database_t,
+        key: notmuch_config_key_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_config_set(
+        notmuch: *mut notmuch_database_t,
+        key: notmuch_config_key_t,
+        val: *const ::std::os::raw::c_char,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_config_get_values(
+        notmuch: *mut notmuch_database_t,
+        key: notmuch_config_key_t,
+    ) -> *mut notmuch_config_values_t;
+}
+extern "C" {
+    pub fn notmuch_config_get_values_string(
+        notmuch: *mut notmuch_database_t,
+        key: *const ::std::os::raw::c_char,
+    ) -> *mut notmuch_config_values_t;
+}
+extern "C" {
+    pub fn notmuch_config_values_valid(values: *mut notmuch_config_values_t) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_config_values_get(
+        values: *mut notmuch_config_values_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_config_values_move_to_next(values: *mut notmuch_config_values_t);
+}
+extern "C" {
+    pub fn notmuch_config_values_start(values: *mut notmuch_config_values_t);
+}
+extern "C" {
+    pub fn notmuch_config_values_destroy(values: *mut notmuch_config_values_t);
+}
+extern "C" {
+    pub fn notmuch_config_get_pairs(
+        notmuch: *mut notmuch_database_t,
+        prefix: *const ::std::os::raw::c_char,
+    ) -> *mut notmuch_config_pairs_t;
+}
+extern "C" {
+    pub fn notmuch_config_pairs_valid(pairs: *mut notmuch_config_pairs_t) -> notmuch_bool_t;
+}
+extern "C" {
+    pub fn notmuch_config_pairs_move_to_next(pairs: *mut notmuch_config_pairs_t);
+}
+extern "C" {
+    pub fn notmuch_config_pairs_key(
+        pairs: *mut notmuch_config_pairs_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_config_pairs_value(
+        pairs: *mut notmuch_config_pairs_t,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_config_pairs_destroy(pairs: *mut notmuch_config_pairs_t);
+}
+extern "C" {
+    pub fn notmuch_config_get_bool(
+        notmuch: *mut notmuch_database_t,
+        key: notmuch_config_key_t,
+        val: *mut notmuch_bool_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_config_path(notmuch: *mut notmuch_database_t) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn notmuch_database_get_default_indexopts(
+        db: *mut notmuch_database_t,
+    ) -> *mut notmuch_indexopts_t;
+}
+pub const notmuch_decryption_policy_t_NOTMUCH_DECRYPT_FALSE: notmuch_decryption_policy_t = 0;
+pub const notmuch_decryption_policy_t_NOTMUCH_DECRYPT_TRUE: notmuch_decryption_policy_t = 1;
+pub const notmuch_decryption_policy_t_NOTMUCH_DECRYPT_AUTO: notmuch_decryption_policy_t = 2;
+pub const notmuch_decryption_policy_t_NOTMUCH_DECRYPT_NOSTASH: notmuch_decryption_policy_t = 3;
+pub type notmuch_decryption_policy_t = u32;
+extern "C" {
+    pub fn notmuch_indexopts_set_decrypt_policy(
+        indexopts: *mut notmuch_indexopts_t,
+        decrypt_policy: notmuch_decryption_policy_t,
+    ) -> notmuch_status_t;
+}
+extern "C" {
+    pub fn notmuch_indexopts_get_decrypt_policy(
+        indexopts: *const notmuch_indexopts_t,
+    ) -> notmuch_decryption_policy_t;
+}
+extern "C" {
+    pub fn notmuch_indexopts_destroy(options: *mut notmuch_indexopts_t);
+}
+extern "C" {
+    pub fn notmuch_built_with(name: *const ::std::os::raw::c_char) -> notmuch_bool_t;
+}
@@ -0,0 +1,93 @@
+//! Checks the checked-in bindings against ones freshly generated from the installed `notmuch.h`.
+//!
+//! Run with `cargo test -p sys --features bindgen`.
+#![cfg(feature = "bindgen")]
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Top-level items of a bindings file, with whitespace normalized so formatting differences
+/// between bindgen and rustfmt versions don't matter. Attributes and comments are skipped, and
+/// the functions of `extern` blocks are taken one by one.
+fn items(source: &str) -> BTreeSet<String> {
+    let code = source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#') && !line.starts_with("//") && !line.starts_with("/*"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut items = BTreeSet::new();
+    let mut item = String::new();
+    // Open blocks, `true` for `extern` blocks.
+    let mut blocks = Vec::new();
+
+    for c in code.chars() {
+        match c {
+            '{' if item.trim() == "extern \"C\"" => {
+                blocks.push(true);
+                item.clear();
+            }
+            '{' => {
+                blocks.push(false);
+                item.push(c);
+            }
+            '}' if blocks.last() == Some(&true) => {
+                blocks.pop();
+                item.clear();
+            }
+            '}' | ';' => {
+                if c == '}' {
+                    blocks.pop();
+                }
+                item.push(c);
+                // Only the end of an item outside of any struct body.
+                if blocks.iter().all(|&block| block) {
+                    items.insert(normalize(&item));
+                    item.clear();
+                }
+            }
+            _ => item.push(c),
+        }
+    }
+
+    items
+}
+
+fn normalize(item: &str) -> String {
+    item.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(",)", ")")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(",>", ">")
+        .replace(", }", " }")
+}
+
+#[test]
+fn checked_in_bindings_match_header() {
+    let generated = fs::read_to_string(concat!(env!("OUT_DIR"), "/bindings.rs")).unwrap();
+    let checked_in_path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(env!("NOTMUCH_CHECKED_IN_BINDINGS"));
+    let checked_in = fs::read_to_string(&checked_in_path).unwrap();
+
+    let generated = items(&generated);
+    let checked_in = items(&checked_in);
+
+    let missing: Vec<_> = generated.difference(&checked_in).collect();
+    let extra: Vec<_> = checked_in.difference(&generated).collect();
+
+    assert!(
+        missing.is_empty() && extra.is_empty(),
+        "{} is out of date with the installed notmuch.h\n\nmissing:\n{:#?}\n\nextra:\n{:#?}\n\n\
+         Regenerated bindings are in {}/bindings.rs",
+        checked_in_path.display(),
+        missing,
+        extra,
+        env!("OUT_DIR"),
+    );
+}
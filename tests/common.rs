@@ -2,6 +2,9 @@
 // Due to how cargo compiles tests, some modules will report dead code.
 #![allow(dead_code)]
 use maildir::Maildir;
+use notmuch_rs::db::Database;
+#[cfg(notmuch_0_32)]
+use notmuch_rs::new;
 use std::ffi::OsStr;
 use std::fs;
 use std::io::Write;
@@ -17,11 +20,36 @@ pub struct TestEnv {
     pub nm_cfg: PathBuf,
 }
 
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TestEnv {
-    pub fn add_email(&self, email: lettre::Message) {
-        self.maildir
+    /// Store `email` in the maildir, returning the path of its file.
+    pub fn add_email(&self, email: lettre::Message) -> PathBuf {
+        let id = self
+            .maildir
             .store_cur_with_flags(&email.formatted(), "")
             .unwrap();
+
+        self.maildir.path().join("cur").join(format!("{}:2,", id))
+    }
+
+    /// Store an empty message from `jdoe@example` to `jdoe2@example` in the maildir, returning
+    /// the path of its file.
+    ///
+    /// The message gets the message ID `id` if given, or else a generated one.
+    pub fn add_message(&self, id: Option<&str>) -> PathBuf {
+        let email = lettre::Message::builder()
+            .from("jdoe@example".parse().unwrap())
+            .to("jdoe2@example".parse().unwrap())
+            .message_id(id.map(|id| format!("<{}>", id)))
+            .body(String::new())
+            .unwrap();
+
+        self.add_email(email)
     }
 
     pub fn new() -> Self {
@@ -33,9 +61,9 @@ impl TestEnv {
         fs::create_dir(&mail_path).unwrap();
 
         // Create maildir folders
-        fs::create_dir(&mail_path.join("cur")).unwrap();
-        fs::create_dir(&mail_path.join("new")).unwrap();
-        fs::create_dir(&mail_path.join("tmp")).unwrap();
+        fs::create_dir(mail_path.join("cur")).unwrap();
+        fs::create_dir(mail_path.join("new")).unwrap();
+        fs::create_dir(mail_path.join("tmp")).unwrap();
 
         let maildir = Maildir::from(mail_path.clone());
 
@@ -54,16 +82,17 @@ impl TestEnv {
         )
         .unwrap();
 
-        let env = Self {
+        // Create the database and scan the empty maildir, like `notmuch new` would.
+        let db = Database::create(&mail_path).unwrap();
+        #[cfg(notmuch_0_32)]
+        new::run(&db).unwrap();
+        db.close().unwrap();
+
+        Self {
             root: tmp_dir,
             maildir,
             nm_cfg,
-        };
-
-        // Create the database.
-        env.notmuch(["new"]);
-
-        env
+        }
     }
 
     /// Call the `notmuch` binary.
@@ -0,0 +1,51 @@
+#[cfg(notmuch_0_32)]
+use notmuch_rs::config::ConfigKey;
+use notmuch_rs::db;
+
+mod common;
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn config_set_get() {
+    let env = common::TestEnv::new();
+
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadWrite).unwrap();
+    db.set_config("user.primary_email", "jdoe@example").unwrap();
+
+    assert_eq!(
+        Ok(String::from("jdoe@example")),
+        db.config("user.primary_email")
+    );
+    assert_eq!(
+        Some(String::from("jdoe@example")),
+        db.config_key(ConfigKey::PrimaryEmail)
+    );
+}
+
+#[test]
+fn config_list_prefix() {
+    let env = common::TestEnv::new();
+
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadWrite).unwrap();
+    db.set_config("user.name", "John Doe").unwrap();
+    db.set_config("user.primary_email", "jdoe@example").unwrap();
+    db.set_config("query.inbox", "tag:inbox").unwrap();
+
+    let keys: Vec<String> = db.config_list("user.").unwrap().map(|(k, _)| k).collect();
+
+    assert_eq!(vec!["user.name", "user.primary_email"], keys);
+}
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn config_values_list() {
+    let env = common::TestEnv::new();
+
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadWrite).unwrap();
+    db.set_config_values(ConfigKey::ExcludeTags, &["deleted", "spam"])
+        .unwrap();
+
+    let tags: Vec<String> = db.config_values(ConfigKey::ExcludeTags).unwrap().collect();
+
+    assert_eq!(vec!["deleted", "spam"], tags);
+}
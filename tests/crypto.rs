@@ -0,0 +1,88 @@
+#![cfg(notmuch_0_32)]
+use notmuch_rs::{
+    crypto::{self, SessionKeyError},
+    db::{Database, OpenMode},
+    new,
+};
+
+mod common;
+
+fn open_db(env: &common::TestEnv) -> Database {
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    new::run(&db).unwrap();
+    db
+}
+
+#[test]
+fn add_and_remove_session_keys() {
+    let env = common::TestEnv::new();
+    env.add_message(Some("secret@example"));
+    let db = open_db(&env);
+    let msg = db.find_message("secret@example").unwrap().unwrap();
+
+    assert!(msg.session_keys().is_empty());
+    msg.add_session_key("9:0123ABCD").unwrap();
+    msg.add_session_key("9:4567EF01").unwrap();
+    assert_eq!(vec!["9:0123ABCD", "9:4567EF01"], msg.session_keys());
+
+    msg.remove_session_key("9:0123ABCD").unwrap();
+    assert_eq!(vec!["9:4567EF01"], msg.session_keys());
+}
+
+#[test]
+fn export_session_keys() {
+    let env = common::TestEnv::new();
+    env.add_message(Some("b@example"));
+    env.add_message(Some("a@example"));
+    env.add_message(Some("plain@example"));
+    let db = open_db(&env);
+    for id in &["a@example", "b@example"] {
+        let msg = db.find_message(id).unwrap().unwrap();
+        msg.add_session_key("9:0123ABCD").unwrap();
+    }
+    let mut out = Vec::new();
+
+    let count = crypto::export_session_keys(&db, &mut out).unwrap();
+
+    assert_eq!(2, count);
+    assert_eq!(
+        "#notmuch-dump batch-tag:3 properties\n\
+         #= a@example session-key=9%3a0123ABCD\n\
+         #= b@example session-key=9%3a0123ABCD\n",
+        String::from_utf8(out).unwrap()
+    );
+}
+
+#[test]
+fn import_session_keys() {
+    let env = common::TestEnv::new();
+    env.add_message(Some("a@example"));
+    let db = open_db(&env);
+    let dump = "#notmuch-dump batch-tag:3 config,properties,tags\n\
+                #@ index.decrypt true\n\
+                #= a@example index.decryption=success session-key=9%3a0123ABCD\n\
+                #= missing@example session-key=9%3a4567EF01\n\
+                +inbox -- id:a@example\n";
+
+    let count = crypto::import_session_keys(&db, dump.as_bytes()).unwrap();
+
+    assert_eq!(1, count);
+    let msg = db.find_message("a@example").unwrap().unwrap();
+    assert_eq!(vec!["9:0123ABCD"], msg.session_keys());
+    assert_eq!(Ok(None), msg.property("index.decryption"));
+}
+
+#[test]
+fn import_invalid_line() {
+    let env = common::TestEnv::new();
+    let db = open_db(&env);
+
+    let res = crypto::import_session_keys(&db, "\n#= a@example session-key\n".as_bytes());
+
+    assert!(matches!(res, Err(SessionKeyError::InvalidLine(2))));
+
+    // `%+a` is not a valid escape, even though `+a` parses as a hexadecimal number.
+    let res = crypto::import_session_keys(&db, "#= a%+a@example session-key=k\n".as_bytes());
+
+    assert!(matches!(res, Err(SessionKeyError::InvalidLine(1))));
+}
@@ -0,0 +1,100 @@
+use notmuch_rs::{
+    date::{interval, DateRange},
+    db,
+    expr::QueryExpr,
+    query::Query,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+mod common;
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[test]
+fn date_range_renders_timestamps() {
+    assert_eq!(
+        "date:@100..@200",
+        DateRange::between(at(100), at(200)).to_expr().to_string()
+    );
+    assert_eq!(
+        "date:@100..",
+        DateRange::since(at(100)).to_expr().to_string()
+    );
+    assert_eq!(
+        "date:..@200",
+        DateRange::parse_at("..@200", at(0))
+            .unwrap()
+            .to_expr()
+            .to_string()
+    );
+}
+
+#[test]
+fn date_relative_intervals() {
+    // 2021-06-16, far from any daylight saving time change.
+    let now = at(1_623_844_800);
+    let day = Duration::from_secs(24 * 60 * 60);
+
+    let today = interval("today", now).unwrap();
+    assert!(today.contains(&now));
+    assert_eq!(day, today.end.duration_since(today.start).unwrap());
+
+    let three_days = interval("3days", now).unwrap();
+    assert_eq!(three_days, interval("3 days ago", now).unwrap());
+    assert_eq!(today.start - 3 * day, three_days.start);
+
+    let yesterday = DateRange::parse_at("yesterday", now).unwrap();
+    assert_eq!(Some(today.start - day), yesterday.since);
+    assert_eq!(Some(today.start - Duration::from_secs(1)), yesterday.until);
+
+    assert!(interval("last fortnight", now).is_err());
+}
+
+#[test]
+fn date_units_and_out_of_range_input() {
+    let now = at(1_623_844_800);
+
+    assert_eq!(interval("3 months", now), interval("3M", now));
+    assert_eq!(interval("3 minutes", now), interval("3m", now));
+    assert_eq!(interval("2 hours", now), interval("2hrs", now));
+    assert_eq!(interval("last hour", now), interval("1 hr ago", now));
+
+    // A bare timestamp is neither a year nor a count of units.
+    assert!(interval("1700000000", now).is_err());
+    assert!(interval("99999999999days", now).is_err());
+    assert!(interval("9223372036854775807 hours ago", now).is_err());
+}
+
+#[test]
+fn date_range_matches_messages() {
+    let env = common::TestEnv::new();
+
+    env.add_message(None);
+    env.notmuch(["new"]);
+
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+
+    let recent: QueryExpr = DateRange::parse("yesterday..now").unwrap().into();
+    let qs = recent.to_string();
+    assert_eq!(
+        1,
+        Query::create(&db, &qs)
+            .search_messages()
+            .unwrap()
+            .into_iter()
+            .count()
+    );
+
+    let old: QueryExpr = DateRange::parse("..last year").unwrap().into();
+    let qs = old.to_string();
+    assert_eq!(
+        0,
+        Query::create(&db, &qs)
+            .search_messages()
+            .unwrap()
+            .into_iter()
+            .count()
+    );
+}
@@ -1,4 +1,6 @@
 use std::path::PathBuf;
+#[cfg(notmuch_0_32)]
+use std::{sync::mpsc, thread, time::Duration};
 
 use notmuch_rs::{db, status::Status};
 
@@ -20,3 +22,125 @@ fn open_db_no_db() {
     assert!(db.is_err());
     assert_eq!(Err(Status::FileError), db);
 }
+
+#[test]
+fn create_db_only_once() {
+    let env = common::TestEnv::new();
+
+    // The test environment already created a database in the maildir.
+    assert!(db::Database::create(env.maildir.path()).is_err());
+
+    let path = env.root.path().join("other");
+    std::fs::create_dir(&path).unwrap();
+    let db = db::Database::create(&path).unwrap();
+    assert_eq!(path, db.path());
+}
+
+#[test]
+fn compact_db_with_backup() {
+    let env = common::TestEnv::new();
+    let backup = env.root.path().join("backup");
+    let mut messages = Vec::new();
+
+    let res = db::Database::compact(env.maildir.path(), Some(&backup), |msg| {
+        messages.push(msg.to_string())
+    });
+
+    assert_eq!(Ok(()), res);
+    assert!(!messages.is_empty());
+    assert!(backup.exists());
+    assert!(db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "stop compacting")]
+fn compact_resumes_panic_of_progress() {
+    let env = common::TestEnv::new();
+
+    let _ = db::Database::compact(env.maildir.path(), None, |_| panic!("stop compacting"));
+}
+
+#[test]
+fn upgrade_current_db() {
+    let env = common::TestEnv::new();
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadWrite).unwrap();
+
+    assert!(!db.needs_upgrade());
+    assert_eq!(Ok(()), db.upgrade(|_| panic!("nothing to upgrade")));
+}
+
+#[test]
+fn close_releases_write_lock() {
+    let env = common::TestEnv::new();
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadWrite).unwrap();
+
+    assert_eq!(Ok(()), db.close());
+    assert!(db::Database::open(env.maildir.path(), db::OpenMode::ReadWrite).is_ok());
+}
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn reopen_read_write() {
+    let env = common::TestEnv::new();
+    let mut db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+
+    assert_eq!(
+        Err(Status::ReadOnlyDatabase),
+        db.set_config("test.key", "value")
+    );
+
+    db.reopen(db::OpenMode::ReadWrite).unwrap();
+    assert_eq!(Ok(()), db.set_config("test.key", "value"));
+
+    db.reopen(db::OpenMode::ReadOnly).unwrap();
+    assert_eq!(Ok("value".to_string()), db.config("test.key"));
+}
+
+#[test]
+fn path_and_version() {
+    let env = common::TestEnv::new();
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+
+    assert_eq!(env.maildir.path(), db.path());
+    assert!(db.version() >= 3);
+}
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn open_with_retry_times_out_while_locked() {
+    let env = common::TestEnv::new();
+    let _writer = db::Database::open(env.maildir.path(), db::OpenMode::ReadWrite).unwrap();
+    let retry = db::LockRetry {
+        timeout: Duration::from_millis(200),
+        ..db::LockRetry::default()
+    };
+
+    let db = db::Database::open_with_retry(env.maildir.path(), db::OpenMode::ReadWrite, &retry);
+
+    assert_eq!(Err(db::OpenError::DatabaseLocked), db);
+}
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn open_with_retry_waits_for_lock() {
+    let env = common::TestEnv::new();
+    let path = env.maildir.path().to_path_buf();
+    let (tx, rx) = mpsc::channel();
+
+    let writer = thread::spawn(move || {
+        let db = db::Database::open(&path, db::OpenMode::ReadWrite).unwrap();
+        tx.send(()).unwrap();
+        thread::sleep(Duration::from_millis(300));
+        drop(db);
+    });
+
+    rx.recv().unwrap();
+    let db = db::Database::open_with_retry(
+        env.maildir.path(),
+        db::OpenMode::ReadWrite,
+        &db::LockRetry::default(),
+    );
+
+    assert!(db.is_ok());
+    writer.join().unwrap();
+}
@@ -0,0 +1,32 @@
+use notmuch_rs::db::{Database, OpenMode};
+use std::path::Path;
+
+mod common;
+
+#[test]
+fn directory_child_files() {
+    let env = common::TestEnv::new();
+
+    env.add_message(None);
+    env.notmuch(["new"]);
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadOnly).unwrap();
+    let dir = db.directory(Path::new("cur")).unwrap().unwrap();
+
+    assert_eq!(1, dir.child_files().unwrap().count());
+    assert!(db.directory(Path::new("missing")).unwrap().is_none());
+}
+
+#[test]
+fn directory_mtime() {
+    let env = common::TestEnv::new();
+
+    env.add_message(None);
+    env.notmuch(["new"]);
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    let dir = db.directory(Path::new("cur")).unwrap().unwrap();
+    dir.set_mtime(1_600_000_000).unwrap();
+
+    assert_eq!(1_600_000_000, dir.mtime());
+}
@@ -0,0 +1,92 @@
+use notmuch_rs::{
+    db,
+    expr::{Prefix, QueryExpr},
+    query::Query,
+};
+
+mod common;
+
+#[test]
+fn expr_quotes_values() {
+    assert_eq!("tag:inbox", QueryExpr::tag("inbox").to_string());
+    assert_eq!(r#"tag:"to do""#, QueryExpr::tag("to do").to_string());
+    assert_eq!(
+        r#"id:"a""b(c)@x""#,
+        QueryExpr::id(r#"a"b(c)@x"#).to_string()
+    );
+    assert_eq!(r#"tag:"""#, QueryExpr::tag("").to_string());
+    assert_eq!(r#""AND""#, QueryExpr::text("AND").to_string());
+    assert_eq!(
+        "property:key=value",
+        QueryExpr::property("key", "value").to_string()
+    );
+}
+
+#[test]
+fn expr_ranges() {
+    assert_eq!("lastmod:5..", QueryExpr::lastmod(Some(5), None).to_string());
+    assert_eq!(
+        r#"date:"2 weeks ago"..today"#,
+        QueryExpr::date(Some("2 weeks ago"), Some("today")).to_string()
+    );
+    assert_eq!(
+        "date:..2021-01-01",
+        QueryExpr::Range(Prefix::Date, None, Some("2021-01-01".to_string())).to_string()
+    );
+}
+
+#[test]
+fn expr_nesting() {
+    let expr = QueryExpr::tag("a")
+        .or(QueryExpr::tag("b"))
+        .and(!QueryExpr::tag("c").and(QueryExpr::tag("d")));
+
+    assert_eq!(
+        "(tag:a or tag:b) and not (tag:c and tag:d)",
+        expr.to_string()
+    );
+    assert_eq!("*", QueryExpr::And(Vec::new()).to_string());
+    assert_eq!("not (not *)", (!QueryExpr::Or(Vec::new())).to_string());
+}
+
+#[test]
+fn expr_matches_tag_with_special_characters() {
+    let env = common::TestEnv::new();
+
+    env.add_message(None);
+    env.notmuch(["new"]);
+
+    let tag = r#"to do (really) "soon""#;
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadWrite).unwrap();
+    let all = Query::create(&db, "*");
+    let msg = all.search_messages().unwrap().into_iter().next().unwrap();
+    msg.add_tag(tag).unwrap();
+
+    let expr = QueryExpr::tag(tag).and(QueryExpr::from("jdoe@example"));
+    let qs = expr.to_string();
+    let query = Query::create(&db, &qs);
+
+    assert_eq!(1, query.search_messages().unwrap().into_iter().count());
+}
+
+#[test]
+fn expr_to_sexp() {
+    let expr = QueryExpr::tag("a")
+        .or(QueryExpr::id(r#"x"y\z@example"#))
+        .and(QueryExpr::date(Some("2 weeks ago"), None));
+
+    assert_eq!(
+        r#"(and (or (tag a) (id "x\"y\\z@example")) (date "2 weeks ago" *))"#,
+        expr.to_sexp()
+    );
+    assert_eq!("()", QueryExpr::All.to_sexp());
+    assert_eq!(r#"(tag "")"#, QueryExpr::tag("").to_sexp());
+    assert_eq!(
+        "(thread (of (List notmuch)))",
+        QueryExpr::thread_of(QueryExpr::header("List", "notmuch")).to_sexp()
+    );
+    assert_eq!(
+        r#"(infix "subject:foo*")"#,
+        QueryExpr::Raw("subject:foo*".to_string()).to_sexp()
+    );
+}
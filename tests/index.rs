@@ -0,0 +1,144 @@
+#[cfg(notmuch_0_32)]
+use notmuch_rs::new;
+use notmuch_rs::{
+    db::{Database, OpenMode},
+    index::{DecryptPolicy, IndexError},
+    status::Status,
+};
+use std::fs;
+
+mod common;
+
+#[test]
+fn default_decrypt_policy_is_auto() {
+    let env = common::TestEnv::new();
+    let db = Database::open(env.maildir.path(), OpenMode::ReadOnly).unwrap();
+
+    let options = db.default_index_options().unwrap();
+
+    assert_eq!(DecryptPolicy::Auto, options.decrypt_policy());
+}
+
+#[test]
+fn set_decrypt_policy() {
+    let env = common::TestEnv::new();
+    let db = Database::open(env.maildir.path(), OpenMode::ReadOnly).unwrap();
+    let options = db.default_index_options().unwrap();
+
+    for &policy in &[
+        DecryptPolicy::False,
+        DecryptPolicy::True,
+        DecryptPolicy::NoStash,
+        DecryptPolicy::Auto,
+    ] {
+        assert_eq!(Ok(()), options.set_decrypt_policy(policy));
+        assert_eq!(policy, options.decrypt_policy());
+    }
+}
+
+#[test]
+fn index_file_with_options() {
+    let env = common::TestEnv::new();
+    env.add_message(Some("index@example"));
+    let file = fs::read_dir(env.maildir.path().join("cur"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    let options = db.default_index_options().unwrap();
+    options.set_decrypt_policy(DecryptPolicy::False).unwrap();
+
+    let (message, duplicate) = db.index_file_with_options(&file.path(), &options).unwrap();
+
+    assert!(!duplicate);
+    assert_eq!("index@example", message.message_id());
+}
+
+#[test]
+fn index_file_with_options_read_only() {
+    let env = common::TestEnv::new();
+    let db = Database::open(env.maildir.path(), OpenMode::ReadOnly).unwrap();
+    let options = db.default_index_options().unwrap();
+
+    let res = db.index_file_with_options(&env.maildir.path().join("cur/missing"), &options);
+
+    assert_eq!(
+        Some(IndexError::Status(Status::ReadOnlyDatabase)),
+        res.err()
+    );
+}
+
+#[test]
+fn crypto_status_to_index_error() {
+    assert_eq!(
+        IndexError::MalformedCryptoProtocol,
+        Status::MalformedCryptoProtocol.into()
+    );
+    assert_eq!(
+        IndexError::CryptoContextCreation,
+        Status::FailedCryptoContextCreation.into()
+    );
+    assert_eq!(
+        IndexError::UnknownCryptoProtocol,
+        Status::UnknownCryptoProtocol.into()
+    );
+    assert_eq!(
+        IndexError::Status(Status::FileError),
+        Status::FileError.into()
+    );
+}
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn reindex_message_keeps_tags() {
+    let env = common::TestEnv::new();
+    env.add_message(Some("reindex@example"));
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    new::run(&db).unwrap();
+
+    let message = db.find_message("reindex@example").unwrap().unwrap();
+    message.add_tag("kept").unwrap();
+    let options = db.default_index_options().unwrap();
+
+    assert_eq!(Ok(()), message.reindex(&options));
+    let message = db.find_message("reindex@example").unwrap().unwrap();
+    assert!(message.get_tags().unwrap().any(|tag| tag == "kept"));
+}
+
+#[test]
+fn reindex_message_read_only() {
+    let env = common::TestEnv::new();
+    env.add_message(Some("reindex@example"));
+    env.notmuch(["new"]);
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadOnly).unwrap();
+    let message = db.find_message("reindex@example").unwrap().unwrap();
+    let options = db.default_index_options().unwrap();
+
+    assert_eq!(
+        Err(IndexError::Status(Status::ReadOnlyDatabase)),
+        message.reindex(&options)
+    );
+}
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn reindex_query_reports_progress() {
+    let env = common::TestEnv::new();
+    env.add_message(Some("one@example"));
+    env.add_message(Some("two@example"));
+    env.add_message(Some("three@example"));
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    new::run(&db).unwrap();
+    let options = db.default_index_options().unwrap();
+    let mut reports = Vec::new();
+
+    let res = db.reindex_query("not id:two@example", &options, |done, total| {
+        reports.push((done, total))
+    });
+
+    assert_eq!(Ok(2), res);
+    assert_eq!(vec![(2, 2)], reports);
+}
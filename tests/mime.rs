@@ -0,0 +1,231 @@
+#![cfg(feature = "mime")]
+use lettre::message::{header::ContentType, Attachment, MultiPart, SinglePart};
+use notmuch_rs::mime::{self, Disposition, MimeError};
+#[cfg(notmuch_0_32)]
+use notmuch_rs::{
+    db::{Database, OpenMode},
+    new,
+};
+
+mod common;
+
+fn mixed_email() -> lettre::Message {
+    lettre::Message::builder()
+        .from("jdoe@example".parse().unwrap())
+        .to("jdoe2@example".parse().unwrap())
+        .message_id(Some("<mime@example>".to_string()))
+        .multipart(
+            MultiPart::mixed()
+                .multipart(MultiPart::alternative_plain_html(
+                    String::from("Hello"),
+                    String::from("<p>Hello</p>"),
+                ))
+                .singlepart(
+                    Attachment::new(String::from("notes.txt"))
+                        .body(String::from("Some notes"), ContentType::TEXT_PLAIN),
+                ),
+        )
+        .unwrap()
+}
+
+const REPORT: &[u8] = &[0x25, 0x50, 0x44, 0x46, 0x00, 0xff, 0x0a, 0x0d];
+
+fn attachments_email() -> lettre::Message {
+    lettre::Message::builder()
+        .from("jdoe@example".parse().unwrap())
+        .to("jdoe2@example".parse().unwrap())
+        .message_id(Some("<attachments@example>".to_string()))
+        .multipart(
+            MultiPart::mixed()
+                .singlepart(SinglePart::plain(String::from("Report attached")))
+                .singlepart(
+                    Attachment::new(String::from("report.pdf"))
+                        .body(REPORT.to_vec(), "application/pdf".parse().unwrap()),
+                )
+                .singlepart(
+                    Attachment::new_inline(String::from("logo"))
+                        .body(vec![0x89, 0x50, 0x4e, 0x47], "image/png".parse().unwrap()),
+                ),
+        )
+        .unwrap()
+}
+
+#[test]
+fn parts_are_numbered_depth_first() {
+    let root = mime::parse(&mixed_email().formatted()).unwrap();
+
+    let parts: Vec<_> = root
+        .iter()
+        .map(|part| (part.number, part.content_type.as_str()))
+        .collect();
+
+    assert_eq!(
+        vec![
+            (0, "message/rfc822"),
+            (1, "multipart/mixed"),
+            (2, "multipart/alternative"),
+            (3, "text/plain"),
+            (4, "text/html"),
+            (5, "text/plain"),
+        ],
+        parts
+    );
+}
+
+#[test]
+fn part_metadata() {
+    let root = mime::parse(&mixed_email().formatted()).unwrap();
+
+    let text = root.find(3).unwrap();
+    assert_eq!(Some("utf-8"), text.charset.as_deref());
+    assert_eq!(None, text.disposition);
+    assert_eq!(None, text.filename);
+    assert_eq!(b"Hello".to_vec(), text.body);
+
+    let attachment = root.find(5).unwrap();
+    assert_eq!(Some(Disposition::Attachment), attachment.disposition);
+    assert_eq!(Some("notes.txt"), attachment.filename.as_deref());
+    assert_eq!(b"Some notes".to_vec(), attachment.body);
+
+    assert!(root.find(6).is_none());
+}
+
+#[test]
+fn whole_message_is_part_zero() {
+    let raw = mixed_email().formatted();
+
+    let root = mime::parse(&raw).unwrap();
+
+    assert_eq!(raw, root.body);
+    assert_eq!(1, root.children.len());
+}
+
+#[test]
+fn embedded_message_body_follows_its_part() {
+    let forwarded = lettre::Message::builder()
+        .from("jdoe2@example".parse().unwrap())
+        .to("jdoe@example".parse().unwrap())
+        .body(String::from("Forwarded"))
+        .unwrap();
+    let email = lettre::Message::builder()
+        .from("jdoe@example".parse().unwrap())
+        .to("jdoe3@example".parse().unwrap())
+        .multipart(
+            MultiPart::mixed()
+                .singlepart(SinglePart::plain(String::from("See below")))
+                .singlepart(
+                    SinglePart::builder()
+                        .content_type("message/rfc822".parse().unwrap())
+                        .body(forwarded.formatted()),
+                ),
+        )
+        .unwrap();
+
+    let root = mime::parse(&email.formatted()).unwrap();
+
+    let embedded = root.find(3).unwrap();
+    assert_eq!("message/rfc822", embedded.content_type);
+    assert_eq!(1, embedded.children.len());
+    let body = root.find(4).unwrap();
+    assert_eq!("text/plain", body.content_type);
+    assert_eq!(b"Forwarded".to_vec(), body.body);
+}
+
+#[test]
+fn write_part_of_large_attachment() {
+    let data: Vec<u8> = (0..8 << 20).map(|i: u32| (i * 7 + i / 251) as u8).collect();
+    let email = lettre::Message::builder()
+        .from("jdoe@example".parse().unwrap())
+        .to("jdoe2@example".parse().unwrap())
+        .multipart(
+            MultiPart::mixed()
+                .singlepart(SinglePart::plain(String::from("Backup attached")))
+                .singlepart(
+                    Attachment::new(String::from("backup.tar"))
+                        .body(data.clone(), "application/x-tar".parse().unwrap()),
+                ),
+        )
+        .unwrap();
+    let raw = email.formatted();
+
+    let mut attachment = Vec::new();
+    mime::write_part(&raw, 3, &mut attachment).unwrap();
+    let mut text = Vec::new();
+    mime::write_part(&raw, 2, &mut text).unwrap();
+
+    assert_eq!(data, attachment);
+    assert_eq!(b"Backup attached".to_vec(), text);
+    assert!(matches!(
+        mime::write_part(&raw, 4, Vec::new()),
+        Err(MimeError::NoSuchPart(4))
+    ));
+}
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn message_parts() {
+    let env = common::TestEnv::new();
+    env.add_email(mixed_email());
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    new::run(&db).unwrap();
+    let message = db.find_message("mime@example").unwrap().unwrap();
+
+    let root = message.parts().unwrap();
+
+    assert_eq!(
+        Some("notes.txt"),
+        root.find(5).and_then(|part| part.filename.as_deref())
+    );
+}
+
+#[test]
+fn attachments_exclude_inline_parts() {
+    let root = mime::parse(&attachments_email().formatted()).unwrap();
+
+    assert_eq!(
+        vec![mime::Attachment {
+            number: 3,
+            filename: Some("report.pdf".to_string()),
+            content_type: "application/pdf".to_string(),
+            size: REPORT.len(),
+        }],
+        root.attachments()
+    );
+}
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn message_attachments() {
+    let env = common::TestEnv::new();
+    env.add_email(attachments_email());
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    new::run(&db).unwrap();
+    let message = db.find_message("attachments@example").unwrap().unwrap();
+
+    let attachments = message.attachments().unwrap();
+
+    assert_eq!(1, attachments.len());
+    assert_eq!(Some("report.pdf"), attachments[0].filename.as_deref());
+}
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn write_part() {
+    let env = common::TestEnv::new();
+    env.add_email(attachments_email());
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    new::run(&db).unwrap();
+    let message = db.find_message("attachments@example").unwrap().unwrap();
+
+    let mut attachment = Vec::new();
+    message.write_part(3, &mut attachment).unwrap();
+    let mut whole = Vec::new();
+    message.write_part(0, &mut whole).unwrap();
+
+    assert_eq!(REPORT.to_vec(), attachment);
+    assert_eq!(std::fs::read(message.filename().unwrap()).unwrap(), whole);
+    assert!(matches!(
+        message.write_part(5, Vec::new()),
+        Err(MimeError::NoSuchPart(5))
+    ));
+}
@@ -0,0 +1,109 @@
+#![cfg(notmuch_0_32)]
+use notmuch_rs::{
+    config::ConfigKey,
+    db::{Database, OpenMode},
+    new,
+    query::Query,
+    status::Status,
+};
+use std::{
+    fs,
+    os::unix::fs::{symlink, PermissionsExt},
+};
+
+mod common;
+
+#[test]
+fn new_adds_messages_with_new_tags() {
+    let env = common::TestEnv::new();
+    env.add_message(None);
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    let summary = new::run(&db).unwrap();
+
+    assert_eq!(1, summary.added);
+    let query = Query::create(&db, "tag:inbox and tag:unread");
+    assert_eq!(1, query.search_messages().unwrap().into_iter().count());
+}
+
+#[test]
+fn new_detects_renames_and_removals() {
+    let env = common::TestEnv::new();
+    let file = env.add_message(None);
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    assert_eq!(1, new::run(&db).unwrap().added);
+
+    let moved = env
+        .maildir
+        .path()
+        .join("new")
+        .join(file.file_name().unwrap());
+    fs::rename(&file, &moved).unwrap();
+
+    let summary = new::run(&db).unwrap();
+    assert_eq!(0, summary.added);
+    assert_eq!(1, summary.renamed);
+
+    fs::remove_file(&moved).unwrap();
+
+    let summary = new::run(&db).unwrap();
+    assert_eq!(1, summary.removed);
+}
+
+#[test]
+fn new_keeps_indexing_after_a_failed_file() {
+    let env = common::TestEnv::new();
+    let failed = env.add_message(None);
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    // Tags are limited to 200 bytes, so tagging the new message fails.
+    db.set_config_values(ConfigKey::NewTags, &[&"x".repeat(201)])
+        .unwrap();
+    let summary = new::run(&db).unwrap();
+    assert_eq!(vec![(failed, Status::TagTooLong)], summary.errors);
+
+    db.set_config_values(ConfigKey::NewTags, &["inbox"])
+        .unwrap();
+    env.add_message(None);
+    assert_eq!(1, new::run(&db).unwrap().added);
+    drop(db);
+
+    // Changes made within a section left open would be discarded when closing the database.
+    let db = Database::open(env.maildir.path(), OpenMode::ReadOnly).unwrap();
+    let query = Query::create(&db, "tag:inbox");
+    assert_eq!(1, query.search_messages().unwrap().into_iter().count());
+}
+
+#[test]
+fn new_skips_unreadable_directories() {
+    let env = common::TestEnv::new();
+    env.add_message(None);
+
+    let locked = env.maildir.path().join("locked");
+    fs::create_dir(&locked).unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    let summary = new::run(&db).unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+    assert_eq!(1, summary.added);
+    // Permissions don't keep root out.
+    if unsafe { libc::geteuid() } != 0 {
+        assert_eq!(vec![(locked, Status::FileError)], summary.errors);
+    }
+}
+
+#[test]
+fn new_does_not_follow_symlink_loops() {
+    let env = common::TestEnv::new();
+    env.add_message(None);
+    symlink(env.maildir.path(), env.maildir.path().join("loop")).unwrap();
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    let summary = new::run(&db).unwrap();
+
+    assert_eq!(1, summary.added);
+    assert!(summary.errors.is_empty());
+}
@@ -0,0 +1,115 @@
+use notmuch_rs::{
+    expr::{Prefix, QueryExpr},
+    parse::{parse, parse_with_headers, ParseErrorKind},
+};
+
+#[test]
+fn parse_boolean_structure() {
+    assert_eq!(
+        Ok(QueryExpr::text("a").or(QueryExpr::text("b").and(QueryExpr::text("c")))),
+        parse("a or b and c")
+    );
+    assert_eq!(
+        Ok(QueryExpr::tag("inbox").and(!QueryExpr::tag("spam"))),
+        parse("is:inbox -tag:spam")
+    );
+    assert_eq!(Ok(QueryExpr::All), parse(""));
+}
+
+#[test]
+fn parse_quoted_values_and_ranges() {
+    assert_eq!(
+        Ok(QueryExpr::tag(r#"to "do""#)),
+        parse(r#"tag:"to ""do""""#)
+    );
+    assert_eq!(
+        Ok(QueryExpr::Range(
+            Prefix::Date,
+            None,
+            Some("2 weeks ago".to_string())
+        )),
+        parse(r#"date:.."2 weeks ago""#)
+    );
+    assert_eq!(Ok(QueryExpr::lastmod(Some(5), None)), parse("lastmod:5.."));
+}
+
+#[test]
+fn parse_round_trips_rendered_expr() {
+    let expr = QueryExpr::folder("Sent Items")
+        .or(QueryExpr::id(r#"a"b(c)@x"#))
+        .and(!QueryExpr::date(Some("2021-01-01"), None));
+
+    assert_eq!(Ok(expr.clone()), expr.to_string().parse());
+}
+
+#[test]
+fn parse_errors_have_spans() {
+    let err = parse("tag:a and (from:b").unwrap_err();
+    assert_eq!(ParseErrorKind::UnclosedParen, err.kind);
+    assert_eq!(10..11, err.span);
+
+    let err = parse("tag:a and").unwrap_err();
+    assert_eq!(ParseErrorKind::UnexpectedEnd, err.kind);
+    assert_eq!(9..9, err.span);
+
+    let err = parse("lastmod:x..").unwrap_err();
+    assert!(matches!(err.kind, ParseErrorKind::InvalidRange(_)));
+    assert_eq!(8..9, err.span);
+}
+
+#[test]
+fn parse_named_queries_and_user_prefixes() {
+    assert_eq!(Ok(QueryExpr::named("work")), parse("query:work"));
+    assert_eq!(
+        Ok(QueryExpr::header("List", "notmuch").and(QueryExpr::tag("inbox"))),
+        parse_with_headers("List:notmuch tag:inbox", &["List"])
+    );
+
+    // Unknown prefixes are free text, as they are for notmuch.
+    assert_eq!(Ok(QueryExpr::text("re:meeting")), parse("re:meeting"));
+    assert_eq!(Ok(QueryExpr::text("List:notmuch")), parse("List:notmuch"));
+}
+
+#[test]
+fn parse_wildcards_and_proximity() {
+    assert_eq!(
+        Ok(!QueryExpr::Raw("subject:foo*".to_string())),
+        parse("-subject:foo*")
+    );
+
+    let expr = parse("tag:inbox and not alice NEAR/3 bob adj carol").unwrap();
+    assert_eq!(
+        QueryExpr::tag("inbox").and(!QueryExpr::Raw("alice NEAR/3 bob adj carol".to_string())),
+        expr
+    );
+    assert_eq!(
+        "tag:inbox and not (alice NEAR/3 bob adj carol)",
+        expr.to_string()
+    );
+
+    let err = parse("subject:f*o").unwrap_err();
+    assert!(matches!(err.kind, ParseErrorKind::Unsupported(_)));
+    assert_eq!(9..10, err.span);
+
+    let err = parse("alice near (bob)").unwrap_err();
+    assert_eq!(ParseErrorKind::UnexpectedToken("(".to_string()), err.kind);
+}
+
+#[test]
+fn parse_thread_subqueries() {
+    let expr = parse("thread:{tag:inbox and from:alice} -tag:spam").unwrap();
+    assert_eq!(
+        QueryExpr::thread_of(QueryExpr::tag("inbox").and(QueryExpr::from("alice")))
+            .and(!QueryExpr::tag("spam")),
+        expr
+    );
+    assert_eq!(Ok(expr.clone()), expr.to_string().parse());
+
+    let err = parse("thread:{tag:inbox").unwrap_err();
+    assert_eq!(ParseErrorKind::UnclosedBrace, err.kind);
+    assert_eq!(7..17, err.span);
+
+    let err = parse("thread:{tag:a and}").unwrap_err();
+    assert_eq!(ParseErrorKind::UnexpectedEnd, err.kind);
+    assert_eq!(17..17, err.span);
+}
@@ -0,0 +1,58 @@
+#![cfg(notmuch_0_32)]
+use notmuch_rs::{
+    db::{Database, OpenMode},
+    new,
+};
+
+mod common;
+
+fn message_db(env: &common::TestEnv) -> Database {
+    env.add_message(Some("props@example"));
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    new::run(&db).unwrap();
+    db
+}
+
+#[test]
+fn add_and_get_property() {
+    let env = common::TestEnv::new();
+    let db = message_db(&env);
+    let msg = db.find_message("props@example").unwrap().unwrap();
+
+    assert_eq!(Ok(None), msg.property("color"));
+    msg.add_property("color", "blue").unwrap();
+    msg.add_property("color", "red").unwrap();
+    msg.add_property("colorspace", "rgb").unwrap();
+
+    assert!(msg.property("color").unwrap().is_some());
+    let exact: Vec<_> = msg.properties("color", true).unwrap().collect();
+    assert_eq!(
+        vec![
+            ("color".to_string(), "blue".to_string()),
+            ("color".to_string(), "red".to_string())
+        ],
+        exact
+    );
+    assert_eq!(3, msg.properties("color", false).unwrap().count());
+}
+
+#[test]
+fn remove_properties() {
+    let env = common::TestEnv::new();
+    let db = message_db(&env);
+    let msg = db.find_message("props@example").unwrap().unwrap();
+    msg.add_property("color", "blue").unwrap();
+    msg.add_property("color", "red").unwrap();
+    msg.add_property("size", "large").unwrap();
+
+    msg.remove_property("color", "blue").unwrap();
+    assert_eq!(Ok(Some("red".to_string())), msg.property("color"));
+
+    msg.remove_all_properties(Some("color")).unwrap();
+    assert_eq!(Ok(None), msg.property("color"));
+    assert_eq!(Ok(Some("large".to_string())), msg.property("size"));
+
+    msg.remove_all_properties(None).unwrap();
+    assert_eq!(0, msg.properties("", false).unwrap().count());
+}
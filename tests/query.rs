@@ -1,4 +1,11 @@
-use notmuch_rs::{db, query::Query};
+#[cfg(notmuch_0_32)]
+use notmuch_rs::config::ConfigKey;
+use notmuch_rs::{
+    db,
+    expr::QueryExpr,
+    query::{Query, QuerySyntax, Sort},
+    status::Status,
+};
 
 mod common;
 
@@ -6,18 +13,139 @@ mod common;
 fn query_simple() {
     let env = common::TestEnv::new();
 
-    let email = lettre::Message::builder()
-        .from("jdoe@example".parse().unwrap())
-        .to("jdoe2@example".parse().unwrap())
-        .body(String::new())
-        .unwrap();
-
-    env.add_email(email);
+    env.add_message(None);
     env.notmuch(["new"]);
 
-    let db = db::Database::open(&env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
     let query = Query::create(&db, "from:jdoe@example");
     let cnt = query.search_messages().into_iter().count();
 
     assert_eq!(1, cnt);
 }
+
+#[test]
+#[cfg(notmuch_0_32)]
+fn query_builder_excludes_configured_tags() {
+    let env = common::TestEnv::new();
+
+    env.add_message(Some("alice@example"));
+    env.add_message(Some("bob@example"));
+    env.notmuch(["new"]);
+    env.notmuch(["tag", "+deleted", "id:alice@example"]);
+
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadWrite).unwrap();
+    db.set_config_values(ConfigKey::ExcludeTags, &["deleted"])
+        .unwrap();
+
+    let query = Query::builder(&db, "to:jdoe2@example").build().unwrap();
+    assert_eq!(1, query.search_messages().unwrap().into_iter().count());
+
+    let query = Query::builder(&db, "to:jdoe2@example")
+        .exclude_tags(&[])
+        .build()
+        .unwrap();
+    assert_eq!(2, query.search_messages().unwrap().into_iter().count());
+}
+
+#[test]
+fn query_builder_offset_limit() {
+    let env = common::TestEnv::new();
+
+    for _ in 0..5 {
+        env.add_message(None);
+    }
+    env.notmuch(["new"]);
+
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+    let query = Query::builder(&db, "from:jdoe@example")
+        .sort(Sort::MessageId)
+        .offset(3)
+        .limit(5)
+        .build()
+        .unwrap();
+
+    assert_eq!(2, query.search_messages().unwrap().into_iter().count());
+}
+
+#[test]
+fn query_pages() {
+    let env = common::TestEnv::new();
+
+    for _ in 0..5 {
+        env.add_message(None);
+    }
+    env.notmuch(["new"]);
+
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+    let query = Query::create(&db, "from:jdoe@example");
+
+    let page = query.search_messages_page(0, 3).unwrap();
+    assert_eq!(3, page.len());
+    assert!(page.has_more());
+
+    let page = query.search_messages_page(3, 3).unwrap();
+    assert_eq!(2, page.len());
+    assert!(!page.has_more());
+
+    let page = query.search_threads_page(4, 3).unwrap();
+    assert_eq!(1, page.len());
+    assert!(!page.has_more());
+}
+
+#[test]
+fn query_modified_since() {
+    let env = common::TestEnv::new();
+
+    env.add_message(Some("alice@example"));
+    env.add_message(Some("bob@example"));
+    env.notmuch(["new"]);
+
+    let (revision, uuid) = {
+        let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+        db.revision()
+    };
+
+    env.notmuch(["tag", "+replied", "id:alice@example"]);
+
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+    assert!(db.revision().0 > revision);
+
+    let query = Query::modified_since(&db, &uuid, revision).unwrap();
+    assert_eq!(1, query.search_messages().unwrap().into_iter().count());
+
+    assert!(Query::modified_since(&db, "some-other-uuid", revision).is_none());
+}
+
+#[test]
+fn query_with_sexp_syntax() {
+    let env = common::TestEnv::new();
+
+    env.add_message(None);
+    env.notmuch(["new"]);
+
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+    let expr = QueryExpr::from("jdoe@example");
+    let query = Query::create_with_syntax(&db, &expr.to_sexp(), QuerySyntax::Sexp);
+
+    if QuerySyntax::sexp_supported() {
+        let cnt = query
+            .unwrap()
+            .search_messages()
+            .unwrap()
+            .into_iter()
+            .count();
+        assert_eq!(1, cnt);
+    } else {
+        assert_eq!(Some(Status::UnsupportedOperation), query.err());
+    }
+}
+
+#[test]
+fn query_with_xapian_syntax() {
+    let env = common::TestEnv::new();
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+
+    let query = Query::create_with_syntax(&db, "tag:inbox", QuerySyntax::Xapian);
+
+    assert!(query.is_ok());
+}
@@ -0,0 +1,94 @@
+#![cfg(all(feature = "mime", notmuch_0_32))]
+use notmuch_rs::{
+    config::ConfigKey,
+    db::{Database, OpenMode},
+    new,
+    reply::{self, Address, ReplyMode},
+};
+
+mod common;
+
+fn setup(env: &common::TestEnv) -> Database {
+    env.add_email(
+        lettre::Message::builder()
+            .from("Alice <alice@example>".parse().unwrap())
+            .to("John Doe <jdoe@example>".parse().unwrap())
+            .to("bob@example".parse().unwrap())
+            .cc("carol@example".parse().unwrap())
+            .cc("jdoe@work.example".parse().unwrap())
+            .subject("Lunch")
+            .message_id(Some("<lunch@example>".to_string()))
+            .references("<plans@example>".to_string())
+            .body(String::from("Noon?\n\nAlice"))
+            .unwrap(),
+    );
+
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    db.set_config_key(ConfigKey::UserName, "John Doe").unwrap();
+    db.set_config_key(ConfigKey::PrimaryEmail, "jdoe@example")
+        .unwrap();
+    db.set_config_values(ConfigKey::OtherEmail, &["jdoe@work.example"])
+        .unwrap();
+    new::run(&db).unwrap();
+
+    db
+}
+
+fn address(address: &str) -> Address {
+    Address {
+        name: None,
+        address: address.to_string(),
+    }
+}
+
+#[test]
+fn reply_to_sender() {
+    let env = common::TestEnv::new();
+    let db = setup(&env);
+    let message = db.find_message("lunch@example").unwrap().unwrap();
+
+    let reply = reply::create(&db, &message, ReplyMode::Sender).unwrap();
+
+    assert_eq!("John Doe <jdoe@example>", reply.from.to_string());
+    assert_eq!("Re: Lunch", reply.subject);
+    assert_eq!(
+        vec![Address {
+            name: Some("Alice".to_string()),
+            address: "alice@example".to_string(),
+        }],
+        reply.to
+    );
+    assert!(reply.cc.is_empty());
+    assert_eq!("<lunch@example>", reply.in_reply_to);
+    assert_eq!("<plans@example> <lunch@example>", reply.references);
+}
+
+#[test]
+fn reply_to_all_excludes_own_addresses() {
+    let env = common::TestEnv::new();
+    let db = setup(&env);
+    let message = db.find_message("lunch@example").unwrap().unwrap();
+
+    let reply = reply::create(&db, &message, ReplyMode::All).unwrap();
+
+    let to: Vec<_> = reply.to.iter().map(|to| to.address.as_str()).collect();
+    assert_eq!(vec!["alice@example", "bob@example"], to);
+    assert_eq!(vec![address("carol@example")], reply.cc);
+}
+
+#[test]
+fn reply_quotes_body() {
+    let env = common::TestEnv::new();
+    let db = setup(&env);
+    let message = db.find_message("lunch@example").unwrap().unwrap();
+
+    let reply = reply::create(&db, &message, ReplyMode::Sender).unwrap();
+
+    assert!(reply.body.starts_with("On "));
+    assert!(reply
+        .body
+        .ends_with("Alice <alice@example> wrote:\n> Noon?\n>\n> Alice\n"));
+    assert!(reply
+        .to_string()
+        .contains("\nIn-Reply-To: <lunch@example>\n"));
+}
@@ -0,0 +1,124 @@
+use notmuch_rs::{
+    db::{Database, OpenMode},
+    sync::{self, Strategy, SyncState, SyncSummary},
+};
+
+mod common;
+
+fn tags(env: &common::TestEnv, id: &str) -> Vec<String> {
+    let db = Database::open(env.maildir.path(), OpenMode::ReadOnly).unwrap();
+    let msg = db.find_message(id).unwrap().unwrap();
+    let mut tags: Vec<String> = msg.get_tags().unwrap().collect();
+    tags.sort();
+    tags
+}
+
+/// Synchronize the databases of `laptop` and `server`, keeping the state next to the laptop's.
+fn sync_envs(laptop: &common::TestEnv, server: &common::TestEnv) -> SyncSummary {
+    let state_path = laptop.root.path().join("sync-state");
+    let local = Database::open(laptop.maildir.path(), OpenMode::ReadWrite).unwrap();
+    let remote = Database::open(server.maildir.path(), OpenMode::ReadWrite).unwrap();
+    let mut state = SyncState::load(&state_path).unwrap();
+
+    let summary = sync::sync(&local, &remote, &mut state, &Strategy::Union).unwrap();
+    state.save(&state_path).unwrap();
+
+    summary
+}
+
+/// Returns a laptop and a server holding the same message, synchronized once.
+fn synced_envs() -> (common::TestEnv, common::TestEnv) {
+    let laptop = common::TestEnv::new();
+    let server = common::TestEnv::new();
+
+    for env in [&laptop, &server] {
+        env.add_message(Some("sync@example"));
+        env.notmuch(["new"]);
+    }
+
+    assert_eq!(SyncSummary::default(), sync_envs(&laptop, &server));
+
+    (laptop, server)
+}
+
+#[test]
+fn sync_tags_both_ways() {
+    let laptop = common::TestEnv::new();
+    let server = common::TestEnv::new();
+
+    for env in [&laptop, &server] {
+        env.add_message(Some("sync@example"));
+        env.notmuch(["new"]);
+    }
+
+    laptop.notmuch(["tag", "+important", "id:sync@example"]);
+
+    let summary = sync_envs(&laptop, &server);
+
+    assert_eq!(0, summary.local_updated);
+    assert_eq!(1, summary.remote_updated);
+    // Without a previous agreement, the policy decides about `important`.
+    assert_eq!(1, summary.conflicts);
+    assert_eq!(
+        vec!["important", "inbox", "unread"],
+        tags(&server, "sync@example")
+    );
+
+    server.notmuch(["tag", "-inbox", "id:sync@example"]);
+
+    let summary = sync_envs(&laptop, &server);
+
+    assert_eq!(1, summary.local_updated);
+    assert_eq!(0, summary.conflicts);
+    assert_eq!(vec!["important", "unread"], tags(&laptop, "sync@example"));
+}
+
+#[test]
+fn sync_local_removal() {
+    let (laptop, server) = synced_envs();
+    laptop.notmuch(["tag", "-inbox", "id:sync@example"]);
+
+    let summary = sync_envs(&laptop, &server);
+
+    assert_eq!(0, summary.local_updated);
+    assert_eq!(1, summary.remote_updated);
+    assert_eq!(0, summary.conflicts);
+    assert_eq!(vec!["unread"], tags(&laptop, "sync@example"));
+    assert_eq!(vec!["unread"], tags(&server, "sync@example"));
+}
+
+#[test]
+fn sync_local_addition() {
+    let (laptop, server) = synced_envs();
+    laptop.notmuch(["tag", "+todo", "id:sync@example"]);
+
+    let summary = sync_envs(&laptop, &server);
+
+    assert_eq!(0, summary.local_updated);
+    assert_eq!(1, summary.remote_updated);
+    assert_eq!(0, summary.conflicts);
+    assert_eq!(
+        vec!["inbox", "todo", "unread"],
+        tags(&server, "sync@example")
+    );
+}
+
+#[test]
+fn sync_remote_addition() {
+    let (laptop, server) = synced_envs();
+    server.notmuch(["tag", "+todo", "id:sync@example"]);
+
+    let summary = sync_envs(&laptop, &server);
+
+    assert_eq!(1, summary.local_updated);
+    assert_eq!(0, summary.remote_updated);
+    assert_eq!(0, summary.conflicts);
+    assert_eq!(
+        vec!["inbox", "todo", "unread"],
+        tags(&laptop, "sync@example")
+    );
+    assert_eq!(
+        vec!["inbox", "todo", "unread"],
+        tags(&server, "sync@example")
+    );
+}
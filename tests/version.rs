@@ -0,0 +1,56 @@
+use notmuch_rs::{
+    db,
+    version::{self, Capability},
+};
+
+mod common;
+
+#[test]
+fn compiled_version() {
+    let compiled = version::compiled();
+
+    assert_eq!(5, compiled.major);
+    assert_eq!(
+        format!("{}.{}.{}", compiled.major, compiled.minor, compiled.micro),
+        compiled.to_string()
+    );
+}
+
+#[test]
+fn runtime_version_not_older_than_compiled() {
+    // Only known when linked dynamically against a fully versioned library file.
+    if let Some(runtime) = version::runtime() {
+        assert_eq!(version::compiled().major, runtime.major);
+        assert!(runtime >= version::compiled());
+    }
+}
+
+#[test]
+fn built_with_unknown_feature() {
+    assert!(!version::built_with("no_such_feature"));
+}
+
+#[test]
+fn has_feature() {
+    let env = common::TestEnv::new();
+    let db = db::Database::open(env.maildir.path(), db::OpenMode::ReadOnly).unwrap();
+
+    assert!(db.has_feature(Capability::Lastmod));
+    assert!(db.has_feature(Capability::Properties));
+    assert_eq!(
+        cfg!(notmuch_0_32),
+        db.has_feature(Capability::IndexedConfig)
+    );
+    assert_eq!(
+        version::built_with("sexp_queries"),
+        db.has_feature(Capability::SexpQueries)
+    );
+}
+
+#[test]
+fn old_database_format_lacks_features() {
+    assert!(!Capability::Lastmod.available_with(2));
+    assert!(!Capability::Properties.available_with(2));
+    assert!(Capability::Lastmod.available_with(3));
+    assert!(Capability::Properties.available_with(3));
+}
@@ -0,0 +1,61 @@
+#![cfg(all(feature = "watch", notmuch_0_32))]
+use notmuch_rs::{
+    db::{Database, OpenMode},
+    watch::{WatchEvent, Watcher},
+};
+use std::{sync::mpsc, thread, time::Duration};
+
+mod common;
+
+#[test]
+fn watcher_indexes_delivered_mail() {
+    let env = common::TestEnv::new();
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    let mut watcher = Watcher::new(&db)
+        .unwrap()
+        .debounce(Duration::from_millis(50));
+
+    let (tx, rx) = mpsc::channel();
+
+    let (delivered, event) = thread::scope(|scope| {
+        let env = &env;
+        let client = scope.spawn(move || {
+            let delivered = env.add_message(None);
+            let event = rx.recv().unwrap();
+
+            // Dropping the receiver stops the watcher after the next change.
+            drop(rx);
+            env.add_message(None);
+
+            (delivered, event)
+        });
+
+        watcher.run(&db, &tx).unwrap();
+
+        client.join().unwrap()
+    });
+
+    match event {
+        WatchEvent::Added(path) => assert_eq!(delivered, path),
+        event => panic!("unexpected event {:?}", event),
+    }
+}
+
+#[test]
+fn watcher_stops_when_asked() {
+    let env = common::TestEnv::new();
+    let db = Database::open(env.maildir.path(), OpenMode::ReadWrite).unwrap();
+    let mut watcher = Watcher::new(&db).unwrap();
+    let stop = watcher.stop_handle();
+
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        stop.stop();
+    });
+
+    // Returns without any change to the mail root.
+    watcher.run(&db, &tx).unwrap();
+    assert!(rx.try_recv().is_err());
+}